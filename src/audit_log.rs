@@ -0,0 +1,375 @@
+use std::collections::BTreeSet;
+
+use chrono::{FixedOffset, NaiveDate, SecondsFormat, Utc};
+use google_sheets4::{Sheets, api::ValueRange};
+use hmac_sha256::HMAC;
+use rand::{Rng, thread_rng};
+use serde_json::Value;
+use yup_oauth2::{hyper::client::HttpConnector, hyper_rustls::HttpsConnector};
+
+use crate::{
+    AppConfig,
+    data_updater::quote_sheet_name,
+    dry_run::CellChange,
+    error::AppResult,
+    executor::{RetryPolicy, execute},
+    helpers::print_table,
+    init::audit_key_path,
+    interaction::get_user_input_audit_filter,
+    sheet_parser::get_sheet_ids,
+    template_builder::generate_sheet,
+};
+
+const AUDIT_LOG_COLUMNS: [&str; 10] = [
+    "Timestamp",
+    "User",
+    "Sheet",
+    "Cell",
+    "Habit",
+    "Date",
+    "Old",
+    "New",
+    "Source",
+    "Chain",
+];
+const CHAIN_COLUMN: usize = 9;
+/// The history view only prints the newest matching entries.
+const MAX_SHOWN_ENTRIES: usize = 50;
+
+pub enum AuditFilter {
+    All,
+    Habit(String),
+    /// Matches both the tracked day and the day the change was made.
+    Date(NaiveDate),
+}
+
+/// Appends one AuditLog row per change. Each row ends with a chain value, an
+/// HMAC of its cells and the previous row's chain keyed with a secret kept on
+/// this machine, so editing, inserting or removing rows in the sheet breaks the
+/// chain from that row on. Rows written from a machine with another key break
+/// it too, unless the key file is copied there. The cells are already written by now, so failing to
+/// log them only warns.
+pub async fn append_audit_log(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    source: &str,
+    changes: &[CellChange],
+) {
+    if changes.is_empty() || app_config.dry_run {
+        return;
+    }
+
+    if let Err(err) = append_entries(hub, app_config, source, changes).await {
+        eprintln!("⚠️  Couldn't write the audit log: {err}");
+    }
+}
+
+async fn append_entries(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    source: &str,
+    changes: &[CellChange],
+) -> AppResult<()> {
+    let sheet_name = &app_config.audit_sheet_name;
+    let sheet_ids = get_sheet_ids(hub, app_config).await?;
+    let key = get_or_create_audit_key(app_config).await?;
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut previous_chain = String::new();
+    if sheet_ids.contains_key(sheet_name) {
+        let range = format!("{}!J:J", quote_sheet_name(sheet_name));
+        let chain_column = execute(
+            hub.spreadsheets()
+                .values_get(&app_config.spreadsheet_id, &range)
+                .delegate(&mut RetryPolicy::default())
+                .doit(),
+        )
+        .await?
+        .values
+        .unwrap_or_default();
+
+        // the first row is the header
+        previous_chain = chain_column
+            .iter()
+            .skip(1)
+            .last()
+            .and_then(|row| row.first())
+            .and_then(|cell| cell.as_str())
+            .unwrap_or_default()
+            .to_string();
+    } else {
+        generate_sheet(hub, app_config, sheet_name, None).await?;
+        rows.push(
+            AUDIT_LOG_COLUMNS
+                .iter()
+                .map(|column| column.to_string())
+                .collect(),
+        );
+    }
+
+    let timestamp = match FixedOffset::east_opt(app_config.utc_offset.num_seconds() as i32) {
+        Some(offset) => Utc::now()
+            .with_timezone(&offset)
+            .to_rfc3339_opts(SecondsFormat::Secs, true),
+        None => Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+    };
+    let user = app_config
+        .user
+        .clone()
+        .unwrap_or_else(|| app_config.profile_name.clone());
+
+    for change in changes {
+        let (sheet, cell) = split_range(&change.range);
+        let mut row = vec![
+            timestamp.clone(),
+            user.clone(),
+            sheet,
+            cell,
+            change.habit.clone(),
+            change.date.clone(),
+            change.old.clone(),
+            change.new.clone(),
+            source.to_string(),
+        ];
+
+        previous_chain = chain(&key, &previous_chain, &row);
+        row.push(previous_chain.clone());
+        rows.push(row);
+    }
+
+    let range = format!("{}!A1", quote_sheet_name(sheet_name));
+    let value_range = ValueRange {
+        range: Some(range.clone()),
+        values: Some(
+            rows.into_iter()
+                .map(|row| row.into_iter().map(Value::String).collect())
+                .collect(),
+        ),
+        ..Default::default()
+    };
+
+    // RAW keeps values like TRUE or 2026-03-01 exactly as they were hashed
+    execute(
+        hub.spreadsheets()
+            .values_append(value_range, &app_config.spreadsheet_id, &range)
+            .value_input_option("RAW")
+            .insert_data_option("INSERT_ROWS")
+            .delegate(&mut RetryPolicy::default())
+            .doit(),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Prints the newest AuditLog entries matching a filter picked by the user,
+/// after checking the chain for rows that were tampered with.
+pub async fn print_audit_log(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+) -> AppResult<()> {
+    let sheet_name = &app_config.audit_sheet_name;
+    if !get_sheet_ids(hub, app_config)
+        .await?
+        .contains_key(sheet_name)
+    {
+        println!("No changes logged yet. Your chronicle awaits its first entry 📜");
+        return Ok(());
+    }
+
+    let range = format!("{}!A:J", quote_sheet_name(sheet_name));
+    let values = execute(
+        hub.spreadsheets()
+            .values_get(&app_config.spreadsheet_id, &range)
+            .delegate(&mut RetryPolicy::default())
+            .doit(),
+    )
+    .await?
+    .values
+    .unwrap_or_default();
+
+    let entries: Vec<Vec<String>> = values
+        .iter()
+        .skip(1)
+        .map(|row| {
+            (0..AUDIT_LOG_COLUMNS.len())
+                .map(|i| {
+                    row.get(i)
+                        .and_then(|cell| cell.as_str())
+                        .unwrap_or_default()
+                        .to_string()
+                })
+                .collect()
+        })
+        .collect();
+
+    if entries.is_empty() {
+        println!("No changes logged yet. Your chronicle awaits its first entry 📜");
+        return Ok(());
+    }
+
+    let key = read_audit_key(app_config).await?;
+    let mut previous_chain = String::new();
+    let mut broken_at: Option<usize> = None;
+    for (i, entry) in entries.iter().enumerate() {
+        let Some(key) = &key else {
+            break;
+        };
+
+        let expected = chain(key, &previous_chain, &entry[..CHAIN_COLUMN]);
+        if entry[CHAIN_COLUMN] != expected {
+            broken_at = Some(i);
+            break;
+        }
+        previous_chain = expected;
+    }
+
+    let habits: BTreeSet<String> = entries
+        .iter()
+        .map(|entry| entry[4].clone())
+        .filter(|habit| !habit.is_empty())
+        .collect();
+
+    let filter = get_user_input_audit_filter(&habits)?;
+
+    let matching: Vec<&Vec<String>> = entries
+        .iter()
+        .filter(|entry| match &filter {
+            AuditFilter::All => true,
+            AuditFilter::Habit(habit) => entry[4] == *habit,
+            AuditFilter::Date(date) => {
+                let date = date.to_string();
+                entry[5] == date || entry[0].starts_with(&date)
+            }
+        })
+        .collect();
+
+    println!();
+    if matching.is_empty() {
+        println!("No logged changes match that filter.");
+    } else {
+        let mut rows: Vec<Vec<String>> = vec![vec![
+            String::from("Timestamp"),
+            String::from("User"),
+            String::from("Habit"),
+            String::from("Date"),
+            String::from("Cell"),
+            String::from("Old"),
+            String::from(""),
+            String::from("New"),
+            String::from("Source"),
+        ]];
+        for entry in matching
+            .iter()
+            .skip(matching.len().saturating_sub(MAX_SHOWN_ENTRIES))
+        {
+            rows.push(vec![
+                entry[0].clone(),
+                entry[1].clone(),
+                entry[4].clone(),
+                entry[5].clone(),
+                format!("{}!{}", quote_sheet_name(&entry[2]), entry[3]),
+                entry[6].clone(),
+                String::from("→"),
+                entry[7].clone(),
+                entry[8].clone(),
+            ]);
+        }
+        print_table(&rows);
+
+        if matching.len() > MAX_SHOWN_ENTRIES {
+            println!("…and {} older entries.", matching.len() - MAX_SHOWN_ENTRIES);
+        }
+    }
+
+    println!();
+    match broken_at {
+        _ if key.is_none() => println!(
+            "⚠️  The chain can't be checked without the key at {}, which signed these entries.",
+            audit_key_path(&app_config.profile_name).display()
+        ),
+        // +2 for the header and 1-based rows
+        Some(i) => {
+            println!(
+                "⚠️  The audit chain breaks at row {} of '{}'. Entries from there on were \
+                 edited, inserted or removed outside this app, or written from another \
+                 machine, which signs with its own key.",
+                i + 2,
+                sheet_name
+            );
+            println!(
+                "   If several machines or teammates write to this log, copy {} to each of \
+                 them so they all sign with the same key.",
+                audit_key_path(&app_config.profile_name).display()
+            );
+        }
+        None => println!(
+            "🔒 All {} entries check out, the chain is intact.",
+            entries.len()
+        ),
+    }
+
+    Ok(())
+}
+
+/// The profile's AuditLog key, if one was made on this machine.
+async fn read_audit_key(app_config: &AppConfig) -> AppResult<Option<Vec<u8>>> {
+    let key_path = audit_key_path(&app_config.profile_name);
+    if !key_path.exists() {
+        return Ok(None);
+    }
+
+    let key = tokio::fs::read_to_string(&key_path).await?;
+    Ok(Some(key.trim().as_bytes().to_vec()))
+}
+
+/// The profile's AuditLog key, made from 32 random bytes on first use and
+/// readable only by the current user.
+async fn get_or_create_audit_key(app_config: &AppConfig) -> AppResult<Vec<u8>> {
+    if let Some(key) = read_audit_key(app_config).await? {
+        return Ok(key);
+    }
+
+    let key: String = thread_rng()
+        .r#gen::<[u8; 32]>()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    let key_path = audit_key_path(&app_config.profile_name);
+    tokio::fs::write(&key_path, &key).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600)).await?;
+    }
+
+    Ok(key.into_bytes())
+}
+
+fn chain(key: &[u8], previous_chain: &str, cells: &[String]) -> String {
+    let mut mac = HMAC::new(key);
+    mac.update(previous_chain);
+    for cell in cells {
+        mac.update([0x1e]);
+        mac.update(cell);
+    }
+
+    mac.finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Splits `'Sheet'!A1` into the unquoted sheet name and the cell address.
+pub fn split_range(range: &str) -> (String, String) {
+    let Some((sheet_name, cell)) = range.rsplit_once('!') else {
+        return (String::new(), range.to_string());
+    };
+
+    let sheet_name = sheet_name
+        .strip_prefix('\'')
+        .and_then(|name| name.strip_suffix('\''))
+        .map_or_else(|| sheet_name.to_string(), |name| name.replace("''", "'"));
+
+    (sheet_name, cell.to_string())
+}
@@ -0,0 +1,99 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use google_sheets4::Sheets;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use yup_oauth2::{hyper::client::HttpConnector, hyper_rustls::HttpsConnector};
+
+use crate::{
+    AppConfig,
+    drive::get_spreadsheet_version,
+    init::{AuthMode, app_config_dir},
+};
+
+/// Set after the spreadsheet's Drive version couldn't be read, so the rest of
+/// the session reads the sheet directly instead of failing the check each loop.
+static VERSION_UNAVAILABLE: AtomicBool = AtomicBool::new(false);
+
+#[derive(Serialize, Deserialize)]
+pub struct CachedSheet {
+    pub values: Vec<Vec<Value>>,
+    /// Drive's version of the spreadsheet when `values` were read or written.
+    pub version: String,
+}
+
+pub fn cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .map(|dir| dir.join("habit_tracker"))
+        .unwrap_or_else(|| app_config_dir().join("cache"))
+}
+
+fn cache_file_path(app_config: &AppConfig) -> PathBuf {
+    let key = format!("{}/{}", app_config.spreadsheet_id, app_config.sheet_name);
+    cache_dir().join(format!("{:016x}.json", checksum(key.as_bytes())))
+}
+
+/// The spreadsheet version to check the cache against, or `None` to skip the
+/// cache. OAuth logins always skip it, as the Drive scope would send the user
+/// through a second consent screen.
+pub async fn get_cache_version(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+) -> Option<String> {
+    if app_config.auth_mode == AuthMode::Oauth || VERSION_UNAVAILABLE.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    match get_spreadsheet_version(hub, &app_config.spreadsheet_id).await {
+        Ok(version) => Some(version),
+        Err(err) => {
+            VERSION_UNAVAILABLE.store(true, Ordering::Relaxed);
+            println!(
+                "ℹ️  Caching is off for this session, the Drive API didn't answer: {}",
+                err
+            );
+            None
+        }
+    }
+}
+
+pub fn read_cached_sheet(app_config: &AppConfig) -> Option<CachedSheet> {
+    let contents = fs::read_to_string(cache_file_path(app_config)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Stores year sheet values along with the spreadsheet version they match.
+pub fn write_cached_sheet(app_config: &AppConfig, values: &[Vec<Value>], version: String) {
+    let cached_sheet = CachedSheet {
+        values: values.to_vec(),
+        version,
+    };
+
+    store(app_config, &cached_sheet);
+}
+
+/// Forces the next `ensure_sheet_ready` to refetch, e.g. after cells were written.
+pub fn clear_cached_sheet(app_config: &AppConfig) {
+    let _ = fs::remove_file(cache_file_path(app_config));
+}
+
+fn store(app_config: &AppConfig, cached_sheet: &CachedSheet) {
+    // the cache is only an optimisation, so failing to write it is not an error
+    let Ok(contents) = serde_json::to_string(cached_sheet) else {
+        return;
+    };
+    if fs::create_dir_all(cache_dir()).is_ok() {
+        let _ = fs::write(cache_file_path(app_config), contents);
+    }
+}
+
+/// 64-bit FNV-1a.
+pub fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,176 @@
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+
+pub enum Command {
+    Interactive,
+    Init,
+    Undo,
+    History,
+    Report,
+    Export,
+    Import,
+}
+
+#[derive(Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Xlsx,
+    Ods,
+    Ics,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Ods => "ods",
+            ExportFormat::Ics => "ics",
+        }
+    }
+}
+
+pub struct CliArgs {
+    pub command: Command,
+    pub profile: Option<String>,
+    pub dry_run: bool,
+    /// Directory for `report --html`.
+    pub html_dir: Option<PathBuf>,
+    pub export_format: Option<ExportFormat>,
+    /// Only export these habits, all of them when empty.
+    pub habits: Vec<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub output: Option<PathBuf>,
+    /// One calendar event per day instead of per completion in `export --format ics`.
+    pub daily_summary: bool,
+    /// File read by `import`.
+    pub import_path: Option<PathBuf>,
+}
+
+pub fn parse_args() -> CliArgs {
+    let mut args = std::env::args().skip(1);
+
+    let mut command = Command::Interactive;
+    let mut profile = None;
+    let mut dry_run = false;
+    let mut html_dir = None;
+    let mut export_format = None;
+    let mut habits = Vec::new();
+    let mut from = None;
+    let mut to = None;
+    let mut output = None;
+    let mut daily_summary = false;
+    let mut import_path = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "init" => command = Command::Init,
+            "undo" => command = Command::Undo,
+            "history" => command = Command::History,
+            "report" => command = Command::Report,
+            "export" => command = Command::Export,
+            "import" => match args.next() {
+                Some(path) => {
+                    command = Command::Import;
+                    import_path = Some(PathBuf::from(path));
+                }
+                None => exit_with_usage("import requires a file"),
+            },
+            "--dry-run" => dry_run = true,
+            "--html" => match args.next() {
+                Some(dir) => html_dir = Some(PathBuf::from(dir)),
+                None => exit_with_usage("--html requires a directory"),
+            },
+            "--format" => match args.next().as_deref() {
+                Some("csv") => export_format = Some(ExportFormat::Csv),
+                Some("json") => export_format = Some(ExportFormat::Json),
+                Some("xlsx") => export_format = Some(ExportFormat::Xlsx),
+                Some("ods") => export_format = Some(ExportFormat::Ods),
+                Some("ics") => export_format = Some(ExportFormat::Ics),
+                _ => exit_with_usage("--format must be csv, json, xlsx, ods or ics"),
+            },
+            "--habit" => match args.next() {
+                Some(habit) => habits.push(habit),
+                None => exit_with_usage("--habit requires a name"),
+            },
+            "--from" => from = Some(parse_date_arg("--from", args.next())),
+            "--to" => to = Some(parse_date_arg("--to", args.next())),
+            "--daily-summary" => daily_summary = true,
+            "--output" => match args.next() {
+                Some(path) => output = Some(PathBuf::from(path)),
+                None => exit_with_usage("--output requires a path"),
+            },
+            "--profile" => match args.next() {
+                Some(name) => profile = Some(name),
+                None => exit_with_usage("--profile requires a name"),
+            },
+            other => exit_with_usage(&format!("Unknown argument '{other}'")),
+        }
+    }
+
+    if let Command::Report = command
+        && html_dir.is_none()
+    {
+        exit_with_usage("report requires --html <dir>");
+    }
+
+    if let Command::Export = command {
+        match export_format {
+            None => exit_with_usage("export requires --format csv|json|xlsx|ods|ics"),
+            Some(ExportFormat::Xlsx | ExportFormat::Ods)
+                if !habits.is_empty() || from.is_some() || to.is_some() =>
+            {
+                exit_with_usage("--habit, --from and --to only apply to csv, json and ics exports")
+            }
+            Some(
+                ExportFormat::Csv | ExportFormat::Json | ExportFormat::Xlsx | ExportFormat::Ods,
+            ) if daily_summary => exit_with_usage("--daily-summary only applies to ics exports"),
+            _ => {}
+        }
+    }
+
+    CliArgs {
+        command,
+        profile,
+        dry_run,
+        html_dir,
+        export_format,
+        habits,
+        from,
+        to,
+        output,
+        daily_summary,
+        import_path,
+    }
+}
+
+fn parse_date_arg(flag: &str, value: Option<String>) -> NaiveDate {
+    match value.map(|value| NaiveDate::parse_from_str(&value, "%Y-%m-%d")) {
+        Some(Ok(date)) => date,
+        _ => exit_with_usage(&format!("{flag} requires a date like 2026-03-01")),
+    }
+}
+
+fn exit_with_usage(message: &str) -> ! {
+    eprintln!("{message}");
+    eprintln!();
+    eprintln!("Usage: habit_tracker [init | undo | history] [--profile <name>] [--dry-run]");
+    eprintln!("       habit_tracker report --html <dir> [--profile <name>]");
+    eprintln!(
+        "       habit_tracker export --format csv|json [--habit <name>]... \
+         [--from <date>] [--to <date>] [--output <file>] [--profile <name>]"
+    );
+    eprintln!(
+        "       habit_tracker export --format ics [--daily-summary] [--habit <name>]... \
+         [--from <date>] [--to <date>] [--output <file>] [--profile <name>]"
+    );
+    eprintln!("       habit_tracker export --format xlsx|ods [--output <file>] [--profile <name>]");
+    eprintln!(
+        "       habit_tracker import <file.csv|file.json|file.ics> [--profile <name>] [--dry-run]"
+    );
+    std::process::exit(2);
+}
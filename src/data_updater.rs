@@ -1,87 +1,129 @@
 use std::collections::HashMap;
 
-use chrono::{DateTime, Datelike, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use cliclack::{multiselect, select};
 use google_sheets4::{
     Sheets,
-    api::{BatchUpdateValuesRequest, ValueRange},
+    api::{BatchUpdateSpreadsheetRequest, BatchUpdateValuesRequest, Request, ValueRange},
 };
 use serde_json::Value;
 use yup_oauth2::{hyper::client::HttpConnector, hyper_rustls::HttpsConnector};
 
 use crate::{
     AppConfig,
-    interaction::{get_user_input_habit, get_user_input_update_value},
+    audit_log::append_audit_log,
+    cache::clear_cached_sheet,
+    dry_run::{CellChange, print_dry_run},
+    error::{AppError, AppResult},
+    executor::{RetryPolicy, execute},
+    helpers::{delete_rows, group_consecutive, print_table},
+    history::record_session,
+    init::valid_months,
+    interaction::{get_user_input_done_dates, get_user_input_habit, get_user_input_update_value},
     print_activities,
-    sheet_parser::get_habits,
+    sheet_parser::{config_table, get_dates, get_habits, get_sheet_id},
 };
 
+/// A month grid of the year sheet being edited, with where its habit rows and
+/// date columns sit in `values`.
+pub struct MonthGrid<'a> {
+    pub values: &'a mut Vec<Vec<Value>>,
+    pub cur_month: &'a String,
+    pub habits: &'a HashMap<String, usize>,
+    pub dates: &'a HashMap<usize, usize>,
+}
+
+/// The `(habit, date, value)` cells a write will touch, worked out before
+/// anything is sent so a preview shows exactly what gets written.
+pub struct PlannedUpdate {
+    pub targets: Vec<(String, usize, &'static str)>,
+    pub description: String,
+    pub source: &'static str,
+}
+
 pub async fn update_today_progress(
     hub: &Sheets<HttpsConnector<HttpConnector>>,
     app_config: &AppConfig,
     wib: &DateTime<Utc>,
     values: &mut Vec<Vec<Value>>,
     months: &HashMap<String, usize>,
-) {
+) -> AppResult<()> {
     let current_month = wib.format("%B").to_string();
-    let month_index = months.get(&current_month).unwrap();
-    let habits = get_habits(&values, month_index.clone());
-    let mut selected_habits = get_user_input_habit(&habits);
+    let month_index = months
+        .get(&current_month)
+        .ok_or_else(|| AppError::SheetStructure(format!("'{}' grid not found", current_month)))?;
+    let habits = get_habits(values, *month_index);
+    let mut selected_habits = get_user_input_habit(&habits)?;
 
-    let update_value = get_user_input_update_value();
+    let update_value = get_user_input_update_value()?;
 
     selected_habits.iter_mut().for_each(|(_, v)| *v = true);
 
-    update_activities(
-        &HashMap::from([(wib.day() as usize, true)]),
-        &selected_habits,
-        &habits,
-        &HashMap::from([(wib.day() as usize, wib.day() as usize)]),
+    let selected_dates = HashMap::from([(wib.day() as usize, true)]);
+    let dates = HashMap::from([(wib.day() as usize, wib.day() as usize)]);
+    let mut grid = MonthGrid {
         values,
+        cur_month: &current_month,
+        habits: &habits,
+        dates: &dates,
+    };
+    let update = mark_all(
+        &selected_dates,
+        &selected_habits,
         &current_month,
-        &app_config,
-        &hub,
         update_value,
+        "record today",
+    );
+
+    submit_update(
+        hub,
+        app_config,
+        &mut grid,
+        &update,
+        &selected_dates,
+        &selected_habits,
     )
-    .await;
+    .await
 }
 
-pub async fn bulk_update(
-    hub: &Sheets<HttpsConnector<HttpConnector>>,
-    app_config: &AppConfig,
-    values: &mut Vec<Vec<Value>>,
+/// Asks how the selected habits and dates should change and returns the cells
+/// to write, without writing them.
+pub fn plan_bulk_update(
+    grid: &MonthGrid,
     selected_dates: &mut HashMap<usize, bool>,
     selected_habits: &mut HashMap<String, bool>,
-    habits: &HashMap<String, usize>,
-    dates: &HashMap<usize, usize>,
-    cur_month: &String,
-) {
-    let mut is_update_all_selected_selector = select("Mark all selected as done/undone? 🎯");
-    is_update_all_selected_selector = is_update_all_selected_selector.item(true, "yes", "");
-    is_update_all_selected_selector = is_update_all_selected_selector.item(false, "no", "");
+) -> AppResult<PlannedUpdate> {
+    let mut update_mode_selector = select("How should the selection be updated? 🎯");
+    update_mode_selector = update_mode_selector.item(1, "Mark all selected as done/undone", "");
+    update_mode_selector = update_mode_selector.item(2, "Pick habits and dates to mark", "");
+    update_mode_selector = update_mode_selector.item(3, "Toggle each cell 🧮", "");
+
+    let update_mode = update_mode_selector.interact()?;
 
-    let is_submit_all = is_update_all_selected_selector.interact().unwrap();
+    if update_mode == 3 {
+        return plan_matrix_update(grid, selected_dates, selected_habits);
+    }
 
-    if !is_submit_all {
+    if update_mode == 2 {
         let mut habit_selector = multiselect("Select habits");
 
         let mut sorted_habit: Vec<_> = selected_habits.keys().cloned().collect();
         sorted_habit.sort();
         for habit in &sorted_habit {
-            habit_selector = habit_selector.item(habit.clone(), &habit, "");
+            habit_selector = habit_selector.item(habit.clone(), habit, "");
         }
 
-        let keep_habit = habit_selector.interact().unwrap();
+        let keep_habit = habit_selector.interact()?;
 
         let mut date_selector = multiselect("Select dates");
 
         let mut sorted_date: Vec<_> = selected_dates.keys().cloned().collect();
         sorted_date.sort();
         for date in &sorted_date {
-            date_selector = date_selector.item(date.clone(), &date, "");
+            date_selector = date_selector.item(*date, date, "");
         }
 
-        let keep_date = date_selector.interact().unwrap();
+        let keep_date = date_selector.interact()?;
 
         for habit in &keep_habit {
             if let Some(value) = selected_habits.get_mut(habit) {
@@ -99,95 +141,667 @@ pub async fn bulk_update(
         selected_dates.iter_mut().for_each(|(_, v)| *v = true);
     }
 
-    let update_value = get_user_input_update_value();
+    let update_value = get_user_input_update_value()?;
 
-    update_activities(
-        &selected_dates,
-        &selected_habits,
-        &habits,
-        &dates,
-        values,
-        &cur_month,
-        &app_config,
-        &hub,
+    Ok(mark_all(
+        selected_dates,
+        selected_habits,
+        grid.cur_month,
         update_value,
-    )
-    .await;
+        "bulk update",
+    ))
 }
 
-pub async fn update_activities(
+/// Every selected habit × selected date set to `update_value`.
+fn mark_all(
     selected_dates: &HashMap<usize, bool>,
     selected_habits: &HashMap<String, bool>,
-    habits: &HashMap<String, usize>,
-    dates: &HashMap<usize, usize>,
-    values: &mut Vec<Vec<Value>>,
     cur_month: &String,
-    app_config: &AppConfig,
-    hub: &Sheets<
-        yup_oauth2::hyper_rustls::HttpsConnector<yup_oauth2::hyper::client::HttpConnector>,
-    >,
     update_value: bool,
-) {
-    let mut updated_cell: Vec<ValueRange> = Vec::new();
+    source: &'static str,
+) -> PlannedUpdate {
     let update_value = if update_value { "TRUE" } else { "FALSE" };
 
+    let mut targets: Vec<(String, usize, &'static str)> = Vec::new();
     for (habit, is_update) in selected_habits {
         if !is_update {
             continue;
         }
-        let habit = habits.get(habit).unwrap();
-
         for (date, is_update) in selected_dates {
-            if !is_update {
-                continue;
+            if *is_update {
+                targets.push((habit.clone(), *date, update_value));
+            }
+        }
+    }
+
+    PlannedUpdate {
+        description: format!(
+            "Marked {} cells in {} as {}",
+            targets.len(),
+            cur_month,
+            update_value
+        ),
+        targets,
+        source,
+    }
+}
+
+/// Lets the user toggle every selected habit × date cell on its own, starting
+/// from what the grid holds, and returns only the cells that changed.
+fn plan_matrix_update(
+    grid: &MonthGrid,
+    selected_dates: &HashMap<usize, bool>,
+    selected_habits: &HashMap<String, bool>,
+) -> AppResult<PlannedUpdate> {
+    let mut sorted_habit: Vec<&String> = selected_habits.keys().collect();
+    sorted_habit.sort();
+    let mut sorted_date: Vec<usize> = selected_dates.keys().copied().collect();
+    sorted_date.sort();
+
+    let is_done = |habit: &String, date: &usize| {
+        grid.values[grid.habits[habit]]
+            .get(grid.dates[date])
+            .is_some_and(|cell| cell == "TRUE")
+    };
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    {
+        let mut header = vec![grid.cur_month.clone()];
+        header.extend(sorted_date.iter().map(|date| date.to_string()));
+        rows.push(header);
+    }
+    for &habit in &sorted_habit {
+        let mut row = vec![habit.to_string()];
+        row.extend(sorted_date.iter().map(|date| {
+            if is_done(habit, date) {
+                String::from("✅")
+            } else {
+                String::from("·")
+            }
+        }));
+        rows.push(row);
+    }
+    println!();
+    print_table(&rows);
+    println!();
+
+    let mut targets: Vec<(String, usize, &'static str)> = Vec::new();
+    for &habit in &sorted_habit {
+        let done_dates: Vec<usize> = sorted_date
+            .iter()
+            .copied()
+            .filter(|date| is_done(habit, date))
+            .collect();
+        let new_done_dates = get_user_input_done_dates(habit, &sorted_date, &done_dates)?;
+
+        for date in &sorted_date {
+            let was_done = done_dates.contains(date);
+            let now_done = new_done_dates.contains(date);
+            if was_done != now_done {
+                targets.push((
+                    habit.clone(),
+                    *date,
+                    if now_done { "TRUE" } else { "FALSE" },
+                ));
             }
-            let date = dates.get(date).unwrap();
+        }
+    }
+
+    Ok(PlannedUpdate {
+        description: format!("Toggled {} cells in {}", targets.len(), grid.cur_month),
+        targets,
+        source: "matrix editor",
+    })
+}
+
+/// Writes a planned update and shows the selection as it now stands.
+pub async fn submit_update(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    grid: &mut MonthGrid<'_>,
+    update: &PlannedUpdate,
+    selected_dates: &HashMap<usize, bool>,
+    selected_habits: &HashMap<String, bool>,
+) -> AppResult<()> {
+    if update.targets.is_empty() {
+        println!("No cells were selected, nothing to write.");
+        return Ok(());
+    }
+
+    if write_activities(hub, app_config, grid, update).await? {
+        print_activities(
+            selected_dates,
+            selected_habits,
+            grid.habits,
+            grid.dates,
+            grid.values,
+            grid.cur_month,
+            &app_config.sheet_name,
+        );
+    }
 
-            let cell_address = get_cell_address(*habit + 1, *date + 1);
-            set_data(
-                &mut updated_cell,
+    Ok(())
+}
+
+/// Writes each `(habit, date, value)` of the grid's month in one batch, checking
+/// the live layout and cells first. Returns whether `values` now matches the sheet, which
+/// isn't the case on a dry run or when rows or columns moved.
+pub async fn write_activities(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    grid: &mut MonthGrid<'_>,
+    update: &PlannedUpdate,
+) -> AppResult<bool> {
+    let (cur_month, habits, dates) = (grid.cur_month, grid.habits, grid.dates);
+    let values = &mut *grid.values;
+    let targets = &update.targets;
+
+    let header_row = valid_months(values)
+        .get(cur_month)
+        .map_or(0, |index| index - 1);
+    let (live_habits, live_dates) = get_live_layout(hub, app_config, cur_month, header_row).await?;
+
+    let mut missing: Vec<String> = Vec::new();
+    let mut moved: Vec<String> = Vec::new();
+    for (habit, date, _) in targets {
+        let (Some(live_habit), Some(live_date)) = (live_habits.get(habit), live_dates.get(date))
+        else {
+            missing.push(format!("{} on {}", habit, date));
+            continue;
+        };
+
+        if habits.get(habit) != Some(live_habit) || dates.get(date) != Some(live_date) {
+            moved.push(format!("{} on {}", habit, date));
+        }
+    }
+
+    if !missing.is_empty() {
+        clear_cached_sheet(app_config);
+        return Err(AppError::SheetStructure(format!(
+            "{} changed while you were editing and no longer has: {}. Reload and try again",
+            cur_month,
+            missing.join(", ")
+        )));
+    }
+
+    if !moved.is_empty() {
+        println!(
+            "⚠️  Rows or columns in {} moved since the sheet was loaded:\n  {}",
+            cur_month,
+            moved.join("\n  ")
+        );
+
+        let mut remap_selector = select("Write to where these cells are now?");
+        remap_selector = remap_selector.item(true, "Yes, use the new positions 🧭", "");
+        remap_selector = remap_selector.item(false, "No, abort 🚫", "");
+
+        if !remap_selector.interact()? {
+            clear_cached_sheet(app_config);
+            return Err(AppError::UserCancelled);
+        }
+    }
+
+    let live_cells = get_live_cells(
+        hub,
+        app_config,
+        targets
+            .iter()
+            .map(|(habit, date, _)| (live_habits[habit], live_dates[date])),
+    )
+    .await?;
+
+    // cells someone else changed since the snapshot, to something other than what we'd write
+    let mut conflicts = vec![vec![
+        String::from("Habit"),
+        String::from("Date"),
+        String::from("Loaded"),
+        String::from("Now"),
+        String::from("Writing"),
+    ]];
+    for (habit, date, update_value) in targets {
+        let loaded = values[habits[habit]]
+            .get(dates[date])
+            .and_then(|cell| cell.as_str())
+            .unwrap_or_default();
+        let now = live_cells
+            .get(&(live_habits[habit], live_dates[date]))
+            .map_or("", |cell| cell.as_str());
+
+        if now != loaded && now != *update_value {
+            conflicts.push(vec![
+                habit.clone(),
+                date.to_string(),
+                loaded.to_string(),
+                now.to_string(),
                 update_value.to_string(),
-                cell_address,
-                &app_config.sheet_name,
-            );
+            ]);
+        }
+    }
 
-            values[*habit][*date] = Value::String(update_value.to_string());
+    if conflicts.len() > 1 {
+        println!(
+            "⚠️  Someone changed {} of these cells in {} since the sheet was loaded:",
+            conflicts.len() - 1,
+            cur_month
+        );
+        println!();
+        print_table(&conflicts);
+        println!();
+
+        if !app_config.dry_run {
+            let mut overwrite_selector = select("Overwrite their changes?");
+            overwrite_selector = overwrite_selector.item(true, "Yes, write mine ✍️", "");
+            overwrite_selector = overwrite_selector.item(false, "No, abort 🚫", "");
+
+            if !overwrite_selector.interact()? {
+                clear_cached_sheet(app_config);
+                return Err(AppError::UserCancelled);
+            }
         }
     }
 
+    let mut updated_cell: Vec<ValueRange> = Vec::new();
+    let mut updated_index: Vec<(usize, usize, &str)> = Vec::new();
+    let mut changes: Vec<CellChange> = Vec::new();
+    for (habit, date, update_value) in targets {
+        let snapshot_index = (habits[habit], dates[date]);
+        let day = NaiveDate::parse_from_str(
+            &format!("{} {} {}", app_config.year, cur_month, date),
+            "%Y %B %d",
+        )
+        .ok();
+        let habit_name = habit;
+        let habit = live_habits[habit];
+        let date = live_dates[date];
+
+        let cell_address = get_cell_address(habit + 1, date + 1);
+        changes.push(
+            CellChange::new(
+                &app_config.sheet_name,
+                &cell_address,
+                values,
+                snapshot_index,
+                update_value,
+            )
+            .with_subject(habit_name, day),
+        );
+        set_data(
+            &mut updated_cell,
+            update_value.to_string(),
+            cell_address,
+            &app_config.sheet_name,
+        );
+
+        updated_index.push((habit, date, *update_value));
+    }
+
+    if app_config.dry_run {
+        print_dry_run(&changes, &[], &HashMap::new());
+        return Ok(false);
+    }
+
     let batch = BatchUpdateValuesRequest {
         value_input_option: Some("USER_ENTERED".to_string()),
         data: Some(updated_cell),
         ..Default::default()
     };
 
-    let result = hub
-        .spreadsheets()
-        .values_batch_update(batch, &app_config.spreadsheet_id)
-        .doit()
-        .await;
-
-    match result {
-        Ok((_, response)) => {
-            println!(
-                "{} cells updated",
-                response.total_updated_cells.unwrap_or(0)
-            );
-
-            print_activities(
-                &selected_dates,
-                &selected_habits,
-                &habits,
-                &dates,
-                &values,
-                cur_month,
-                &app_config.sheet_name,
-            );
+    let response = execute(
+        hub.spreadsheets()
+            .values_batch_update(batch, &app_config.spreadsheet_id)
+            .delegate(&mut RetryPolicy::default())
+            .doit(),
+    )
+    .await?;
+
+    println!(
+        "{} cells updated",
+        response.total_updated_cells.unwrap_or(0)
+    );
+
+    append_audit_log(hub, app_config, update.source, &changes).await;
+    record_session(app_config, update.description.clone(), changes).await;
+
+    if !moved.is_empty() {
+        // the local snapshot no longer lines up with the sheet, reload it next time round
+        clear_cached_sheet(app_config);
+        println!("🔄 The sheet layout changed, it will be reloaded.");
+        return Ok(false);
+    }
+
+    for (row, col, update_value) in updated_index {
+        set_cell_value(values, row, col, update_value);
+    }
+    // our own write bumped the spreadsheet version, so the cached copy can't be
+    // told apart from one someone else has edited since
+    clear_cached_sheet(app_config);
+
+    Ok(true)
+}
+
+/// Re-reads the `(row, col)` cells about to be written, in one request for
+/// the block that spans them.
+async fn get_live_cells(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    cells: impl Iterator<Item = (usize, usize)>,
+) -> AppResult<HashMap<(usize, usize), String>> {
+    let cells: Vec<(usize, usize)> = cells.collect();
+    let (Some(top), Some(bottom), Some(left), Some(right)) = (
+        cells.iter().map(|(row, _)| *row).min(),
+        cells.iter().map(|(row, _)| *row).max(),
+        cells.iter().map(|(_, col)| *col).min(),
+        cells.iter().map(|(_, col)| *col).max(),
+    ) else {
+        return Ok(HashMap::new());
+    };
+
+    let range = format!(
+        "{}!{}:{}",
+        quote_sheet_name(&app_config.sheet_name),
+        get_cell_address(top + 1, left + 1),
+        get_cell_address(bottom + 1, right + 1)
+    );
+    let block = execute(
+        hub.spreadsheets()
+            .values_get(&app_config.spreadsheet_id, &range)
+            .delegate(&mut RetryPolicy::default())
+            .doit(),
+    )
+    .await?
+    .values
+    .unwrap_or_default();
+
+    Ok(cells
+        .into_iter()
+        .map(|(row, col)| {
+            let cell = block
+                .get(row - top)
+                .and_then(|block_row| block_row.get(col - left))
+                .and_then(|cell| cell.as_str())
+                .unwrap_or_default();
+            ((row, col), cell.to_string())
+        })
+        .collect())
+}
+
+/// Re-reads where `cur_month`'s habit rows and date columns are right now, so
+/// edits made elsewhere since `values` was fetched aren't overwritten or misaddressed.
+async fn get_live_layout(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    cur_month: &String,
+    header_row: usize,
+) -> AppResult<(HashMap<String, usize>, HashMap<usize, usize>)> {
+    let sheet_name = quote_sheet_name(&app_config.sheet_name);
+    let header_range = |row: usize| format!("{}!A{}:AF{}", sheet_name, row + 1, row + 1);
+
+    let response = execute(
+        hub.spreadsheets()
+            .values_batch_get(&app_config.spreadsheet_id)
+            .add_ranges(&format!("{}!A:A", sheet_name))
+            .add_ranges(&header_range(header_row))
+            .delegate(&mut RetryPolicy::default())
+            .doit(),
+    )
+    .await?;
+
+    let mut value_ranges = response
+        .value_ranges
+        .unwrap_or_default()
+        .into_iter()
+        .map(|value_range| value_range.values.unwrap_or_default());
+    let mut live = value_ranges.next().unwrap_or_default();
+    let mut header = value_ranges.next().unwrap_or_default();
+
+    let month_index = *valid_months(&live).get(cur_month).ok_or_else(|| {
+        clear_cached_sheet(app_config);
+        AppError::SheetStructure(format!(
+            "{} was removed from the sheet while you were editing",
+            cur_month
+        ))
+    })?;
+
+    if month_index - 1 != header_row {
+        header = execute(
+            hub.spreadsheets()
+                .values_get(&app_config.spreadsheet_id, &header_range(month_index - 1))
+                .delegate(&mut RetryPolicy::default())
+                .doit(),
+        )
+        .await?
+        .values
+        .unwrap_or_default();
+    }
+
+    if let Some(header) = header.into_iter().next() {
+        live[month_index - 1] = header;
+    }
+
+    Ok((
+        get_habits(&live, month_index),
+        get_dates(&live, month_index),
+    ))
+}
+
+pub async fn update_habit_config(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    values: &Vec<Vec<Value>>,
+) -> AppResult<()> {
+    let config_sheet_name = &app_config.config_sheet_name;
+    let range = format!(
+        "{}!{}",
+        quote_sheet_name(config_sheet_name),
+        config_table::RANGE
+    );
+    let config_sheet = execute(
+        hub.spreadsheets()
+            .values_get(&app_config.spreadsheet_id, &range)
+            .delegate(&mut RetryPolicy::default())
+            .doit(),
+    )
+    .await?;
+
+    let current_month_habits = get_habits(values, 1);
+    let current_month_dates = get_dates(values, 1);
+
+    let config_values = config_sheet.values.ok_or_else(|| {
+        AppError::SheetStructure(format!("'{}' sheet has no values", config_sheet_name))
+    })?;
+
+    let config_habits = get_habits(&config_values, config_table::START_ROW_INDEX);
+    let mut selected_habits = get_user_input_habit(&config_habits)?;
+    selected_habits.iter_mut().for_each(|(_, v)| *v = true);
+
+    let mut update_value_selector = select("Set habit");
+    update_value_selector = update_value_selector.item(true, "Active ✅", "");
+    update_value_selector = update_value_selector.item(false, "Inactive 🚫", "");
+
+    let update_value = update_value_selector.interact()?;
+
+    let mut row_to_delete: Vec<String> = Vec::new();
+    if !update_value {
+        for (habit_name, is_update) in selected_habits.iter_mut() {
+            let cur_month_index = match current_month_habits.get(habit_name) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let mut deletable = true;
+
+            for date_index in current_month_dates.values() {
+                if values[*cur_month_index]
+                    .get(*date_index)
+                    .is_some_and(|cell| cell == "TRUE")
+                {
+                    deletable = false;
+                    *is_update = false;
+                    println!(
+                        "{} has activity history. Deactivation is not allowed.",
+                        habit_name
+                    );
+                    break;
+                }
+            }
+
+            if deletable {
+                row_to_delete.push(habit_name.to_string());
+            }
+        }
+    }
+
+    let update_value = if update_value { "TRUE" } else { "FALSE" };
+
+    let mut updated_cell: Vec<ValueRange> = Vec::new();
+    let mut changes: Vec<CellChange> = Vec::new();
+    for (habit, is_update) in &selected_habits {
+        if !is_update {
+            continue;
+        }
+
+        let habit_name = habit;
+        let habit = config_habits[habit];
+        let cell_address = get_cell_address(habit + 1, config_table::Column::IsActive.as_usize());
+
+        changes.push(
+            CellChange::new(
+                config_sheet_name,
+                &cell_address,
+                &config_values,
+                (
+                    habit,
+                    config_table::Column::IsActive.as_usize_zero_based_index(),
+                ),
+                update_value,
+            )
+            .with_subject(habit_name, None),
+        );
+        set_data(
+            &mut updated_cell,
+            update_value.to_string(),
+            cell_address,
+            config_sheet_name,
+        );
+    }
+
+    let mut delete_row_requests: Vec<Request> = Vec::new();
+    let mut deleted_rows: Vec<CellChange> = Vec::new();
+    let mut sheet_id: i32 = 0;
+    if !row_to_delete.is_empty() {
+        for habit in &row_to_delete {
+            let Some(row) = current_month_habits.get(habit) else {
+                continue;
+            };
+            // the whole row goes, so it's logged as one entry rather than per cell
+            deleted_rows.push(CellChange {
+                range: format!(
+                    "{}!{}:{}",
+                    quote_sheet_name(&app_config.sheet_name),
+                    row + 1,
+                    row + 1
+                ),
+                habit: habit.clone(),
+                date: String::new(),
+                old: habit.clone(),
+                new: String::from("(row deleted)"),
+            });
         }
-        Err(err) => {
-            eprint!("Update failed: {:?}", err);
+
+        let mut habit_to_delete_index: Vec<usize> = row_to_delete
+            .iter()
+            .filter_map(|name| current_month_habits.get(name).copied())
+            .collect();
+
+        habit_to_delete_index.sort();
+
+        sheet_id = get_sheet_id(hub, app_config, &app_config.sheet_name).await?;
+
+        let habit_to_delete_index_group = group_consecutive(&habit_to_delete_index);
+
+        let mut previous_group_deleted_row: i32 = 0;
+        for group in habit_to_delete_index_group {
+            let min_index = (group[0] as i32) - previous_group_deleted_row;
+            let max_index = (group[group.len() - 1] as i32) + 1 - previous_group_deleted_row;
+
+            let delete_request = delete_rows(sheet_id, "ROWS".to_string(), min_index, max_index);
+            delete_row_requests.push(delete_request);
+            previous_group_deleted_row += max_index - min_index;
         }
     }
+
+    if app_config.dry_run {
+        let sheet_names = HashMap::from([(sheet_id, app_config.sheet_name.clone())]);
+        print_dry_run(&changes, &delete_row_requests, &sheet_names);
+        return Ok(());
+    }
+
+    let batch = BatchUpdateValuesRequest {
+        value_input_option: Some("USER_ENTERED".to_string()),
+        data: Some(updated_cell),
+        ..Default::default()
+    };
+
+    let response = execute(
+        hub.spreadsheets()
+            .values_batch_update(batch, &app_config.spreadsheet_id)
+            .delegate(&mut RetryPolicy::default())
+            .doit(),
+    )
+    .await?;
+
+    println!(
+        "{} cells updated",
+        response.total_updated_cells.unwrap_or(0)
+    );
+
+    let mut description = format!("Set {} habits' IsActive to {}", changes.len(), update_value);
+    if !row_to_delete.is_empty() {
+        description.push_str(", their deleted grid rows aren't restored");
+    }
+    append_audit_log(hub, app_config, "habit config", &changes).await;
+    record_session(app_config, description, changes).await;
+
+    if !delete_row_requests.is_empty() {
+        let delete_msg = format!(
+            "\nSuccessfully deleted: \n  {}\n\nUndo can set these habits active again, but \
+             won't bring their rows back.\n",
+            row_to_delete.join("\n  ")
+        );
+
+        let update_batch = BatchUpdateSpreadsheetRequest {
+            requests: Some(delete_row_requests),
+            include_spreadsheet_in_response: None,
+            response_include_grid_data: None,
+            response_ranges: None,
+        };
+
+        execute(
+            hub.spreadsheets()
+                .batch_update(update_batch, &app_config.spreadsheet_id)
+                .delegate(&mut RetryPolicy::default())
+                .doit(),
+        )
+        .await?;
+        clear_cached_sheet(app_config);
+        append_audit_log(hub, app_config, "habit config", &deleted_rows).await;
+
+        println!("{delete_msg}");
+    }
+
+    Ok(())
+}
+
+/// Writes a cell into the local snapshot, padding short rows the API trimmed.
+pub fn set_cell_value(values: &mut Vec<Vec<Value>>, row: usize, col: usize, value: &str) {
+    if values.len() <= row {
+        values.resize(row + 1, Vec::new());
+    }
+    if values[row].len() <= col {
+        values[row].resize(col + 1, Value::String(String::new()));
+    }
+
+    values[row][col] = Value::String(value.to_string());
 }
 
 pub fn set_data(
@@ -198,7 +812,7 @@ pub fn set_data(
 ) {
     let value: Value = Value::String(cell_value.clone());
     value_range.push(ValueRange {
-        range: Some(format!("{}!{}", sheet_name, cell_index)),
+        range: Some(format!("{}!{}", quote_sheet_name(sheet_name), cell_index)),
         values: Some(vec![vec![value]]),
         ..Default::default()
     });
@@ -217,3 +831,8 @@ fn column_to_letter(mut col: usize) -> String {
 pub fn get_cell_address(row: usize, col: usize) -> String {
     format!("{}{}", column_to_letter(col), row)
 }
+
+/// Quotes a sheet name for A1 notation so names like `2026-alice` are not parsed as formulas.
+pub fn quote_sheet_name(sheet_name: &str) -> String {
+    format!("'{}'", sheet_name.replace('\'', "''"))
+}
@@ -0,0 +1,150 @@
+use google_sheets4::{
+    Sheets,
+    client::{Delegate, MethodInfo, Retry},
+};
+use serde_json::{Value, json};
+use yup_oauth2::{
+    hyper::{Body, Method, Request, body, client::HttpConnector, header},
+    hyper_rustls::HttpsConnector,
+};
+
+use crate::{
+    error::{AppError, AppResult},
+    executor::{RetryPolicy, wait_for_quota},
+};
+
+/// Lets the tracker share spreadsheets it created, and nothing else in Drive.
+const DRIVE_FILE_SCOPE: &str = "https://www.googleapis.com/auth/drive.file";
+/// Lets the tracker see when a spreadsheet last changed, but not its contents.
+const DRIVE_METADATA_SCOPE: &str = "https://www.googleapis.com/auth/drive.metadata.readonly";
+
+const FILES_URL: &str = "https://www.googleapis.com/drive/v3/files";
+
+/// Calls the Drive API with the hub's client and credentials, since the Sheets
+/// API has no way to share a spreadsheet or tell when it last changed. Goes
+/// through the same quota and retry policy as the Sheets calls.
+async fn drive_request(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    method_id: &'static str,
+    scope: &str,
+    method: Method,
+    url: &str,
+    body: Option<Value>,
+) -> AppResult<Value> {
+    let token = hub
+        .auth
+        .get_token(&[scope])
+        .await
+        .map_err(|err| AppError::Auth(err.to_string()))?
+        .ok_or_else(|| AppError::Auth(String::from("no token for the Drive API")))?;
+
+    let mut retry_policy = RetryPolicy::default();
+    retry_policy.begin(MethodInfo {
+        id: method_id,
+        http_method: method.clone(),
+    });
+
+    loop {
+        wait_for_quota().await;
+
+        let request = Request::builder()
+            .method(method.clone())
+            .uri(url)
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(
+                body.as_ref()
+                    .map_or_else(Body::empty, |body| Body::from(body.to_string())),
+            )
+            .map_err(|err| AppError::Network(err.to_string()))?;
+
+        retry_policy.pre_request();
+        let response = match hub.client.request(request).await {
+            Ok(response) => response,
+            Err(err) => match retry_policy.http_error(&err) {
+                Retry::After(delay) => {
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+                Retry::Abort => return Err(AppError::Network(err.to_string())),
+            },
+        };
+
+        let status = response.status();
+        if !status.is_success()
+            && let Retry::After(delay) = retry_policy.http_failure(&response, None)
+        {
+            tokio::time::sleep(delay).await;
+            continue;
+        }
+
+        let bytes = body::to_bytes(response.into_body())
+            .await
+            .map_err(|err| AppError::Network(err.to_string()))?;
+        let value: Value = serde_json::from_slice(&bytes).unwrap_or_default();
+
+        if !status.is_success() {
+            let message = value["error"]["message"]
+                .as_str()
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| status.to_string());
+            return Err(AppError::Network(message));
+        }
+
+        return Ok(value);
+    }
+}
+
+/// Grants `email` edit access to a spreadsheet.
+pub async fn share_spreadsheet(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    spreadsheet_id: &str,
+    email: &str,
+) -> AppResult<()> {
+    let url = format!(
+        "{}/{}/permissions?sendNotificationEmail=true",
+        FILES_URL, spreadsheet_id
+    );
+    let permission = json!({ "role": "writer", "type": "user", "emailAddress": email });
+
+    drive_request(
+        hub,
+        "drive.permissions.create",
+        DRIVE_FILE_SCOPE,
+        Method::POST,
+        &url,
+        Some(permission),
+    )
+    .await
+    .map_err(|err| {
+        AppError::Network(format!(
+            "could not share spreadsheet {} with {}: {}",
+            spreadsheet_id, email, err
+        ))
+    })?;
+
+    Ok(())
+}
+
+/// Drive's version number of a spreadsheet, which goes up with every change
+/// anyone makes to any of its tabs.
+pub async fn get_spreadsheet_version(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    spreadsheet_id: &str,
+) -> AppResult<String> {
+    let url = format!("{}/{}?fields=version", FILES_URL, spreadsheet_id);
+    let file = drive_request(
+        hub,
+        "drive.files.get",
+        DRIVE_METADATA_SCOPE,
+        Method::GET,
+        &url,
+        None,
+    )
+    .await?;
+
+    file["version"]
+        .as_str()
+        .map(|version| version.to_string())
+        .ok_or_else(|| AppError::Network(String::from("Drive returned no file version")))
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use google_sheets4::api::{DimensionRange, GridCoordinate, GridRange, Request};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    data_updater::{get_cell_address, quote_sheet_name},
+    helpers::print_table,
+};
+
+/// A single cell a write would touch.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CellChange {
+    pub range: String,
+    /// The habit and `YYYY-MM-DD` date the cell belongs to, empty when it has none.
+    #[serde(default)]
+    pub habit: String,
+    #[serde(default)]
+    pub date: String,
+    pub old: String,
+    pub new: String,
+}
+
+impl CellChange {
+    /// `index` is the (row, column) of the cell in `values`, where the old value is read from.
+    pub fn new(
+        sheet_name: &str,
+        cell_address: &str,
+        values: &[Vec<Value>],
+        index: (usize, usize),
+        new: &str,
+    ) -> CellChange {
+        let old = values
+            .get(index.0)
+            .and_then(|row| row.get(index.1))
+            .and_then(|cell| cell.as_str())
+            .unwrap_or_default();
+
+        CellChange {
+            range: format!("{}!{}", quote_sheet_name(sheet_name), cell_address),
+            habit: String::new(),
+            date: String::new(),
+            old: old.to_string(),
+            new: new.to_string(),
+        }
+    }
+
+    pub fn with_subject(mut self, habit: &str, date: Option<NaiveDate>) -> CellChange {
+        self.habit = habit.to_string();
+        self.date = date.map(|date| date.to_string()).unwrap_or_default();
+        self
+    }
+}
+
+pub fn print_dry_run(
+    changes: &[CellChange],
+    requests: &[Request],
+    sheet_names: &HashMap<i32, String>,
+) {
+    println!();
+    println!("🧪 Dry run, nothing was sent to the sheet.");
+
+    if !changes.is_empty() {
+        println!();
+        println!("Cells that would change:");
+
+        let mut rows: Vec<Vec<String>> = vec![vec![
+            String::from("Range"),
+            String::from("Old"),
+            String::from(""),
+            String::from("New"),
+        ]];
+        for change in changes {
+            let old = if change.old.is_empty() {
+                String::from("(empty)")
+            } else {
+                change.old.clone()
+            };
+            rows.push(vec![
+                change.range.clone(),
+                old,
+                String::from("→"),
+                change.new.clone(),
+            ]);
+        }
+        print_table(&rows);
+    }
+
+    if !requests.is_empty() {
+        println!();
+        println!("Structural requests that would be issued:");
+        for request in requests {
+            println!("  • {}", describe_request(request, sheet_names));
+        }
+    }
+
+    println!();
+}
+
+pub fn describe_request(request: &Request, sheet_names: &HashMap<i32, String>) -> String {
+    let sheet_name = |sheet_id: Option<i32>| {
+        let sheet_id = sheet_id.unwrap_or_default();
+        sheet_names
+            .get(&sheet_id)
+            .map(|name| quote_sheet_name(name))
+            .unwrap_or_else(|| format!("sheet #{}", sheet_id))
+    };
+
+    if let Some(add_sheet) = &request.add_sheet {
+        let props = add_sheet.properties.as_ref();
+        return format!(
+            "add sheet '{}'",
+            props.and_then(|p| p.title.as_deref()).unwrap_or_default()
+        );
+    }
+    if let Some(insert) = &request.insert_dimension {
+        return match &insert.range {
+            Some(range) => format!(
+                "insert {}",
+                describe_dimension(range, sheet_name(range.sheet_id))
+            ),
+            None => String::from("insert rows"),
+        };
+    }
+    if let Some(delete) = &request.delete_dimension {
+        return match &delete.range {
+            Some(range) => format!(
+                "delete {}",
+                describe_dimension(range, sheet_name(range.sheet_id))
+            ),
+            None => String::from("delete rows"),
+        };
+    }
+    if let Some(repeat_cell) = &request.repeat_cell {
+        return match &repeat_cell.range {
+            Some(range) => format!(
+                "format {}",
+                describe_grid_range(range, sheet_name(range.sheet_id))
+            ),
+            None => String::from("format cells"),
+        };
+    }
+    if let Some(validation) = &request.set_data_validation {
+        return match &validation.range {
+            Some(range) => format!(
+                "add checkboxes to {}",
+                describe_grid_range(range, sheet_name(range.sheet_id))
+            ),
+            None => String::from("add checkboxes"),
+        };
+    }
+    if let Some(update_cells) = &request.update_cells {
+        let n_row = update_cells.rows.as_ref().map_or(0, |rows| rows.len());
+        return match &update_cells.start {
+            Some(start) => format!(
+                "write {} rows starting at {}",
+                n_row,
+                describe_grid_coordinate(start, sheet_name(start.sheet_id))
+            ),
+            None => format!("write {} rows", n_row),
+        };
+    }
+    if let Some(auto_resize) = &request.auto_resize_dimensions {
+        return match &auto_resize.dimensions {
+            Some(range) => format!(
+                "auto-resize {}",
+                describe_dimension(range, sheet_name(range.sheet_id))
+            ),
+            None => String::from("auto-resize columns"),
+        };
+    }
+
+    String::from("other request")
+}
+
+fn describe_dimension(range: &DimensionRange, sheet_name: String) -> String {
+    let start = range.start_index.unwrap_or_default() + 1;
+    let end = range.end_index.unwrap_or_default();
+    let dimension = range.dimension.as_deref().unwrap_or("ROWS").to_lowercase();
+
+    format!("{} {}-{} in {}", dimension, start, end, sheet_name)
+}
+
+fn describe_grid_range(range: &GridRange, sheet_name: String) -> String {
+    let start = get_cell_address(
+        range.start_row_index.unwrap_or_default() as usize + 1,
+        range.start_column_index.unwrap_or_default() as usize + 1,
+    );
+    let end = get_cell_address(
+        range.end_row_index.unwrap_or_default() as usize,
+        range.end_column_index.unwrap_or_default() as usize,
+    );
+
+    format!("{}!{}:{}", sheet_name, start, end)
+}
+
+fn describe_grid_coordinate(start: &GridCoordinate, sheet_name: String) -> String {
+    let cell_address = get_cell_address(
+        start.row_index.unwrap_or_default() as usize + 1,
+        start.column_index.unwrap_or_default() as usize + 1,
+    );
+
+    format!("{}!{}", sheet_name, cell_address)
+}
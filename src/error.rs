@@ -0,0 +1,95 @@
+use std::{fmt, io};
+
+use google_sheets4::client;
+
+#[derive(Debug)]
+pub enum AppError {
+    /// config.toml or a credentials file is missing or malformed.
+    Config(String),
+    /// Google refused to hand out a token.
+    Auth(String),
+    /// A Sheets API call failed to go through.
+    Network(String),
+    /// The spreadsheet doesn't have the tabs, rows or cells we expect.
+    SheetStructure(String),
+    /// A file handed to `import` couldn't be read or understood.
+    Import(String),
+    /// History couldn't be turned into the requested export format.
+    Export(String),
+    /// Reading or writing a local file or the terminal failed.
+    Io(String),
+    /// The user pressed Ctrl-C / Esc at a prompt.
+    UserCancelled,
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    /// Exit codes follow sysexits.h where one fits.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::Config(_) => 78,
+            AppError::Auth(_) => 77,
+            AppError::Network(_) => 69,
+            AppError::SheetStructure(_) => 65,
+            AppError::Import(_) => 65,
+            AppError::Export(_) => 70,
+            AppError::Io(_) => 74,
+            AppError::UserCancelled => 130,
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, AppError::Network(_) | AppError::SheetStructure(_))
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Config(msg) => {
+                write!(
+                    f,
+                    "Config problem: {msg}. Run `habit_tracker init` to fix it."
+                )
+            }
+            AppError::Auth(msg) => write!(f, "Could not sign in to Google: {msg}"),
+            AppError::Network(msg) => write!(
+                f,
+                "Could not reach Google Sheets: {msg}. Make sure you have internet connection."
+            ),
+            AppError::SheetStructure(msg) => write!(f, "Unexpected spreadsheet layout: {msg}"),
+            AppError::Import(msg) => write!(f, "Could not import: {msg}"),
+            AppError::Export(msg) => write!(f, "Could not export: {msg}"),
+            AppError::Io(msg) => write!(f, "I/O error: {msg}"),
+            AppError::UserCancelled => write!(f, "Cancelled."),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<client::Error> for AppError {
+    fn from(err: client::Error) -> Self {
+        match err {
+            client::Error::MissingToken(err) => AppError::Auth(err.to_string()),
+            client::Error::BadRequest(value) => {
+                let message = value["error"]["message"]
+                    .as_str()
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| value.to_string());
+                AppError::Network(message)
+            }
+            other => AppError::Network(other.to_string()),
+        }
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::Interrupted => AppError::UserCancelled,
+            _ => AppError::Io(err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::VecDeque,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use google_sheets4::client::{self, Delegate, MethodInfo, Retry};
+use rand::{Rng, thread_rng};
+use yup_oauth2::hyper::{Body, Response, StatusCode, header::RETRY_AFTER};
+
+use crate::error::AppResult;
+
+/// Sheets allows 60 reads and 60 writes per minute per user. Counting both
+/// against a single budget keeps us under either quota.
+const REQUESTS_PER_MINUTE: usize = 60;
+const QUOTA_WINDOW: Duration = Duration::from_secs(60);
+
+const MAX_ATTEMPTS: u32 = 5;
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(32);
+
+/// Start times of every request sent in the last `QUOTA_WINDOW`.
+static REQUEST_LOG: Mutex<VecDeque<Instant>> = Mutex::new(VecDeque::new());
+
+/// Runs a Sheets call built with `.delegate(&mut RetryPolicy::default())`,
+/// waiting first if the per-minute quota is already used up.
+pub async fn execute<T>(
+    call: impl Future<Output = client::Result<(Response<Body>, T)>>,
+) -> AppResult<T> {
+    wait_for_quota().await;
+
+    let (_, response) = call.await?;
+    Ok(response)
+}
+
+/// Sleeps until another request fits into the per-minute quota, for calls
+/// sent without the generated client, like the Drive API ones.
+pub async fn wait_for_quota() {
+    let wait = quota_wait();
+    if !wait.is_zero() {
+        println!(
+            "⏳ Sheets quota reached, waiting {}s before the next request...",
+            wait.as_secs().max(1)
+        );
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// How long until another request fits into the per-minute quota.
+fn quota_wait() -> Duration {
+    let mut log = REQUEST_LOG.lock().unwrap_or_else(|err| err.into_inner());
+    quota_wait_at(&mut log, Instant::now())
+}
+
+/// Drops requests older than `QUOTA_WINDOW` from `log` and returns how long
+/// after `now` the oldest remaining one leaves room for another.
+fn quota_wait_at(log: &mut VecDeque<Instant>, now: Instant) -> Duration {
+    while log
+        .front()
+        .is_some_and(|sent| now.duration_since(*sent) >= QUOTA_WINDOW)
+    {
+        log.pop_front();
+    }
+
+    if log.len() < REQUESTS_PER_MINUTE {
+        return Duration::ZERO;
+    }
+
+    log.front().map_or(Duration::ZERO, |oldest| {
+        QUOTA_WINDOW - now.duration_since(*oldest)
+    })
+}
+
+fn record_request() {
+    let mut log = REQUEST_LOG.lock().unwrap_or_else(|err| err.into_inner());
+    log.push_back(Instant::now());
+}
+
+/// Retries transient failures (5xx, 429 and dropped connections) from inside
+/// the generated client, using jittered exponential backoff.
+#[derive(Default)]
+pub struct RetryPolicy {
+    method: &'static str,
+    attempt: u32,
+}
+
+impl RetryPolicy {
+    fn retry(&mut self, reason: &str, retry_after: Option<Duration>) -> Retry {
+        self.attempt += 1;
+        if self.attempt >= MAX_ATTEMPTS {
+            eprintln!(
+                "⚠️  {} failed ({}), giving up after {} attempts",
+                self.method, reason, self.attempt
+            );
+            return Retry::Abort;
+        }
+
+        let delay = retry_after
+            .unwrap_or_else(|| backoff_delay(self.attempt))
+            .max(quota_wait());
+
+        eprintln!(
+            "🔁 {} failed ({}), retrying in {:.1}s (attempt {}/{})",
+            self.method,
+            reason,
+            delay.as_secs_f64(),
+            self.attempt + 1,
+            MAX_ATTEMPTS
+        );
+
+        Retry::After(delay)
+    }
+}
+
+impl Delegate for RetryPolicy {
+    fn begin(&mut self, info: MethodInfo) {
+        self.method = info.id;
+    }
+
+    fn pre_request(&mut self) {
+        record_request();
+    }
+
+    fn http_error(&mut self, err: &yup_oauth2::hyper::Error) -> Retry {
+        self.retry(&err.to_string(), None)
+    }
+
+    fn http_failure(
+        &mut self,
+        response: &Response<Body>,
+        _err: Option<serde_json::Value>,
+    ) -> Retry {
+        let status = response.status();
+        if status != StatusCode::TOO_MANY_REQUESTS && !status.is_server_error() {
+            return Retry::Abort;
+        }
+
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+
+        self.retry(status.as_str(), retry_after)
+    }
+}
+
+/// `BASE_DELAY * 2^(attempt - 1)`, capped at `MAX_DELAY`, plus up to 50% jitter.
+fn backoff_delay(attempt: u32) -> Duration {
+    let delay = BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt - 1))
+        .min(MAX_DELAY);
+    let jitter = thread_rng().gen_range(0..=delay.as_millis() as u64 / 2);
+
+    delay + Duration::from_millis(jitter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_delay_doubles_with_jitter() {
+        for (attempt, base) in [(1, 500), (2, 1000), (3, 2000), (7, 32_000), (20, 32_000)] {
+            let base = Duration::from_millis(base);
+            for _ in 0..50 {
+                let delay = backoff_delay(attempt);
+                assert!(base <= delay && delay <= base + base / 2, "{delay:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn retry_after_overrides_backoff() {
+        let mut retry_policy = RetryPolicy::default();
+
+        match retry_policy.retry("429", Some(Duration::from_secs(7))) {
+            Retry::After(delay) => assert_eq!(delay, Duration::from_secs(7)),
+            Retry::Abort => panic!("should retry"),
+        }
+    }
+
+    #[test]
+    fn retry_gives_up_after_max_attempts() {
+        let mut retry_policy = RetryPolicy::default();
+        for _ in 1..MAX_ATTEMPTS {
+            assert!(matches!(
+                retry_policy.retry("503", Some(Duration::ZERO)),
+                Retry::After(_)
+            ));
+        }
+
+        assert!(matches!(retry_policy.retry("503", None), Retry::Abort));
+    }
+
+    #[test]
+    fn quota_wait_at_leaves_room_under_limit() {
+        let start = Instant::now();
+        let mut log: VecDeque<Instant> = (0..REQUESTS_PER_MINUTE - 1).map(|_| start).collect();
+
+        assert_eq!(quota_wait_at(&mut log, start), Duration::ZERO);
+    }
+
+    #[test]
+    fn quota_wait_at_waits_for_oldest_request() {
+        let start = Instant::now();
+        let mut log: VecDeque<Instant> = (0..REQUESTS_PER_MINUTE as u64)
+            .map(|i| start + Duration::from_secs(i / 10))
+            .collect();
+        let now = start + Duration::from_secs(20);
+
+        assert_eq!(quota_wait_at(&mut log, now), Duration::from_secs(40));
+        assert_eq!(log.len(), REQUESTS_PER_MINUTE);
+    }
+
+    #[test]
+    fn quota_wait_at_forgets_old_requests() {
+        let start = Instant::now();
+        let mut log: VecDeque<Instant> = (0..REQUESTS_PER_MINUTE).map(|_| start).collect();
+        let now = start + QUOTA_WINDOW;
+
+        assert_eq!(quota_wait_at(&mut log, now), Duration::ZERO);
+        assert!(log.is_empty());
+    }
+}
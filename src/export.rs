@@ -0,0 +1,360 @@
+use std::{collections::HashMap, path::PathBuf, str::FromStr};
+
+use chrono::NaiveDate;
+use google_sheets4::Sheets;
+use serde::Serialize;
+use serde_json::Value;
+use time::Month;
+use yup_oauth2::{hyper::client::HttpConnector, hyper_rustls::HttpsConnector};
+
+use crate::{
+    AppConfig,
+    cli::ExportFormat,
+    data_updater::quote_sheet_name,
+    error::{AppError, AppResult},
+    executor::{RetryPolicy, execute},
+    html_report::get_year_sheets,
+    ical::to_ics,
+    init::valid_months,
+    sheet_parser::{get_dates, get_habits},
+};
+
+const CSV_HEADER: &str = "date,habit,value,note,exp";
+
+/// One habit on one day, the long format pandas and R expect.
+#[derive(Serialize)]
+pub struct ExportRow {
+    /// `YYYY-MM-DD`, which sorts and parses the same everywhere.
+    pub date: String,
+    pub habit: String,
+    pub value: bool,
+    pub note: Option<String>,
+    pub exp: u32,
+}
+
+/// Which rows of the history make it into the export.
+pub struct ExportFilter {
+    pub habits: Vec<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl ExportFilter {
+    fn matches(&self, habit: &str, date: NaiveDate) -> bool {
+        (self.habits.is_empty() || self.habits.iter().any(|name| name == habit))
+            && self.from.is_none_or(|from| from <= date)
+            && self.to.is_none_or(|to| date <= to)
+    }
+}
+
+/// A year tab read with its cell notes, which the values API doesn't return.
+struct YearGrid {
+    year: i32,
+    values: Vec<Vec<Value>>,
+    notes: HashMap<(usize, usize), String>,
+}
+
+async fn get_year_grids(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+) -> AppResult<Vec<YearGrid>> {
+    let year_sheets = get_year_sheets(hub, app_config).await?;
+    if year_sheets.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut retry_policy = RetryPolicy::default();
+    let mut request = hub
+        .spreadsheets()
+        .get(&app_config.spreadsheet_id)
+        .include_grid_data(true)
+        .param(
+            "fields",
+            "sheets(properties.title,data.rowData.values(formattedValue,note))",
+        )
+        .delegate(&mut retry_policy);
+    for (_, sheet_name) in &year_sheets {
+        request = request.add_ranges(&quote_sheet_name(sheet_name));
+    }
+
+    let spreadsheet = execute(request.doit()).await?;
+    let years: HashMap<String, i32> = year_sheets
+        .into_iter()
+        .map(|(year, sheet_name)| (sheet_name, year))
+        .collect();
+
+    let mut grids = Vec::new();
+    for sheet in spreadsheet.sheets.unwrap_or_default() {
+        let Some(year) = sheet
+            .properties
+            .and_then(|props| props.title)
+            .and_then(|title| years.get(&title).copied())
+        else {
+            continue;
+        };
+
+        let mut values = Vec::new();
+        let mut notes = HashMap::new();
+        let rows = sheet
+            .data
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|grid| grid.row_data.unwrap_or_default());
+        for (row, row_data) in rows.enumerate() {
+            let mut row_values = Vec::new();
+            for (col, cell) in row_data.values.unwrap_or_default().into_iter().enumerate() {
+                if let Some(note) = cell.note {
+                    notes.insert((row, col), note);
+                }
+                row_values.push(Value::String(cell.formatted_value.unwrap_or_default()));
+            }
+            values.push(row_values);
+        }
+
+        grids.push(YearGrid {
+            year,
+            values,
+            notes,
+        });
+    }
+
+    Ok(grids)
+}
+
+/// Flattens every month grid of a year tab into one row per habit and day,
+/// leaving out days after `today` that can't have been done yet.
+fn flatten_grid(
+    grid: &YearGrid,
+    filter: &ExportFilter,
+    today: NaiveDate,
+    rows: &mut Vec<ExportRow>,
+) {
+    for (month, index) in valid_months(&grid.values) {
+        let month = match Month::from_str(&month) {
+            Ok(month) => u8::from(month) as u32,
+            Err(_) => continue,
+        };
+
+        let habits = get_habits(&grid.values, index);
+        let dates = get_dates(&grid.values, index);
+
+        for (habit, row) in &habits {
+            for (day, col) in &dates {
+                let Some(date) = NaiveDate::from_ymd_opt(grid.year, month, *day as u32) else {
+                    continue;
+                };
+                if date > today || !filter.matches(habit, date) {
+                    continue;
+                }
+
+                let value = grid.values[*row]
+                    .get(*col)
+                    .is_some_and(|cell| cell == "TRUE");
+                rows.push(ExportRow {
+                    date: date.format("%Y-%m-%d").to_string(),
+                    habit: habit.clone(),
+                    value,
+                    note: grid.notes.get(&(*row, *col)).cloned(),
+                    exp: value as u32,
+                });
+            }
+        }
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn to_csv(rows: &[ExportRow]) -> String {
+    let mut csv = format!("{}\n", CSV_HEADER);
+    for row in rows {
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            row.date,
+            csv_field(&row.habit),
+            row.value,
+            csv_field(row.note.as_deref().unwrap_or_default()),
+            row.exp
+        ));
+    }
+    csv
+}
+
+/// Writes the full completion history of every year tab as CSV or JSON, one
+/// row per habit and day up to `today`, for analysis elsewhere or a backup
+/// outside Google, or as an iCalendar file of completions.
+pub async fn export_history(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    format: ExportFormat,
+    filter: &ExportFilter,
+    daily_summary: bool,
+    output: Option<PathBuf>,
+    today: NaiveDate,
+) -> AppResult<()> {
+    let mut rows = Vec::new();
+    for grid in get_year_grids(hub, app_config).await? {
+        flatten_grid(&grid, filter, today, &mut rows);
+    }
+    rows.sort_by(|a, b| (&a.date, &a.habit).cmp(&(&b.date, &b.habit)));
+
+    let content = match format {
+        ExportFormat::Csv => to_csv(&rows),
+        ExportFormat::Json => serde_json::to_string_pretty(&rows)
+            .map_err(|err| AppError::Export(format!("failed to serialize rows: {err}")))?,
+        ExportFormat::Ics => to_ics(&rows, daily_summary, today, app_config),
+        ExportFormat::Xlsx | ExportFormat::Ods => {
+            unreachable!("Workbooks are written by export_workbook")
+        }
+    };
+
+    let path = output.unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "habit-history-{}.{}",
+            app_config.profile_name,
+            format.extension()
+        ))
+    });
+    tokio::fs::write(&path, content).await?;
+
+    println!(
+        "📦 Exported {} habit-days to {}",
+        rows.len(),
+        path.display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn rows(cells: &[&[&str]]) -> Vec<Vec<Value>> {
+        cells
+            .iter()
+            .map(|row| row.iter().map(|cell| json!(cell)).collect())
+            .collect()
+    }
+
+    fn grid() -> YearGrid {
+        YearGrid {
+            year: 2024,
+            values: rows(&[
+                &["March", "1", "2", "3"],
+                &["Read", "TRUE", "FALSE", "TRUE"],
+                &["Run", "FALSE", "TRUE", "FALSE"],
+            ]),
+            notes: HashMap::from([((1, 2), String::from("too tired"))]),
+        }
+    }
+
+    fn no_filter() -> ExportFilter {
+        ExportFilter {
+            habits: Vec::new(),
+            from: None,
+            to: None,
+        }
+    }
+
+    fn flatten(
+        filter: &ExportFilter,
+        today: NaiveDate,
+    ) -> Vec<(String, String, bool, Option<String>)> {
+        let mut export_rows = Vec::new();
+        flatten_grid(&grid(), filter, today, &mut export_rows);
+
+        let mut flat: Vec<_> = export_rows
+            .into_iter()
+            .map(|row| (row.date, row.habit, row.value, row.note))
+            .collect();
+        flat.sort();
+        flat
+    }
+
+    #[test]
+    fn flatten_grid_skips_future_days_and_attaches_notes() {
+        let flat = flatten(&no_filter(), date(2024, 3, 2));
+
+        assert_eq!(
+            flat,
+            vec![
+                ("2024-03-01".into(), "Read".into(), true, None),
+                ("2024-03-01".into(), "Run".into(), false, None),
+                (
+                    "2024-03-02".into(),
+                    "Read".into(),
+                    false,
+                    Some("too tired".into())
+                ),
+                ("2024-03-02".into(), "Run".into(), true, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn flatten_grid_applies_the_filter() {
+        let filter = ExportFilter {
+            habits: vec![String::from("Run")],
+            from: Some(date(2024, 3, 2)),
+            to: None,
+        };
+
+        let flat = flatten(&filter, date(2024, 12, 31));
+
+        assert_eq!(
+            flat,
+            vec![
+                ("2024-03-02".into(), "Run".into(), true, None),
+                ("2024-03-03".into(), "Run".into(), false, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("Read"), "Read");
+        assert_eq!(csv_field("Read, write"), "\"Read, write\"");
+        assert_eq!(csv_field("the \"big\" one"), "\"the \"\"big\"\" one\"");
+        assert_eq!(csv_field("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn to_csv_writes_header_and_escaped_rows() {
+        let rows = vec![
+            ExportRow {
+                date: String::from("2024-03-01"),
+                habit: String::from("Read, write"),
+                value: true,
+                note: Some(String::from("chapter \"one\"")),
+                exp: 1,
+            },
+            ExportRow {
+                date: String::from("2024-03-02"),
+                habit: String::from("Run"),
+                value: false,
+                note: None,
+                exp: 0,
+            },
+        ];
+
+        assert_eq!(
+            to_csv(&rows),
+            "date,habit,value,note,exp\n\
+             2024-03-01,\"Read, write\",true,\"chapter \"\"one\"\"\",1\n\
+             2024-03-02,Run,false,,0\n"
+        );
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use cliclack::select;
+use console::{Alignment, Key, Term, measure_text_width, pad_str, style};
+use google_sheets4::Sheets;
+use serde_json::Value;
+use yup_oauth2::{hyper::client::HttpConnector, hyper_rustls::HttpsConnector};
+
+use crate::{
+    AppConfig,
+    data_updater::{MonthGrid, PlannedUpdate, set_cell_value, write_activities},
+    error::AppResult,
+    init::valid_months,
+    sheet_parser::{get_completion_history, get_dates, get_habits},
+    stats::{Period, current_streak, done_days, period_exp},
+};
+
+const PANEL_WIDTH: usize = 32;
+
+/// A toggled cell, keyed by month, habit and day of month.
+type PendingCell = (String, String, usize);
+
+struct GridEditor<'a> {
+    values: &'a Vec<Vec<Value>>,
+    /// `values` with the pending toggles applied, used for rendering and stats.
+    edited: Vec<Vec<Value>>,
+    /// Month names ordered as they appear in the sheet, newest first.
+    months: Vec<(String, usize)>,
+    month: usize,
+    row: usize,
+    col: usize,
+    pending: BTreeMap<PendingCell, bool>,
+    year: i32,
+    today: NaiveDate,
+}
+
+impl<'a> GridEditor<'a> {
+    fn habits(&self) -> Vec<(String, usize)> {
+        let mut habits: Vec<(String, usize)> = get_habits(&self.edited, self.months[self.month].1)
+            .into_iter()
+            .collect();
+        habits.sort_by_key(|(_, row)| *row);
+        habits
+    }
+
+    fn dates(&self) -> Vec<(usize, usize)> {
+        let mut dates: Vec<(usize, usize)> = get_dates(&self.edited, self.months[self.month].1)
+            .into_iter()
+            .collect();
+        dates.sort_by_key(|(day, _)| *day);
+        dates
+    }
+
+    fn is_done(values: &[Vec<Value>], row: usize, col: usize) -> bool {
+        values
+            .get(row)
+            .and_then(|cells| cells.get(col))
+            .is_some_and(|cell| cell == "TRUE")
+    }
+
+    fn switch_month(&mut self, month: usize) {
+        self.month = month;
+        self.row = self.row.min(self.habits().len().saturating_sub(1));
+        self.col = self.col.min(self.dates().len().saturating_sub(1));
+    }
+
+    fn toggle(&mut self) {
+        let (Some((habit, row)), Some((day, col))) = (
+            self.habits().get(self.row).cloned(),
+            self.dates().get(self.col).copied(),
+        ) else {
+            return;
+        };
+
+        let is_done = !GridEditor::is_done(&self.edited, row, col);
+        set_cell_value(
+            &mut self.edited,
+            row,
+            col,
+            if is_done { "TRUE" } else { "FALSE" },
+        );
+
+        let key = (self.months[self.month].0.clone(), habit, day);
+        if is_done == GridEditor::is_done(self.values, row, col) {
+            self.pending.remove(&key);
+        } else {
+            self.pending.insert(key, is_done);
+        }
+    }
+
+    fn grid_lines(&self) -> Vec<String> {
+        let (month, _) = &self.months[self.month];
+        let habits = self.habits();
+        let dates = self.dates();
+        let name_width = habits
+            .iter()
+            .map(|(habit, _)| measure_text_width(habit))
+            .max()
+            .unwrap_or(0)
+            .max(8);
+
+        let is_current_month = self.today.format("%B").to_string() == *month;
+
+        let mut lines = vec![
+            format!(
+                "🗓  {} {}  ({}/{})",
+                style(month).bold(),
+                self.year,
+                self.month + 1,
+                self.months.len()
+            ),
+            String::new(),
+        ];
+
+        let mut header = " ".repeat(name_width + 1);
+        for (day, _) in &dates {
+            let label = format!("{:>2} ", day);
+            if is_current_month && *day == self.today.day() as usize {
+                header.push_str(&style(label).bold().underlined().to_string());
+            } else {
+                header.push_str(&style(label).dim().to_string());
+            }
+        }
+        lines.push(header);
+
+        for (i, (habit, row)) in habits.iter().enumerate() {
+            let mut line = format!("{} ", pad_str(habit, name_width, Alignment::Left, None));
+            for (j, (day, col)) in dates.iter().enumerate() {
+                let is_done = GridEditor::is_done(&self.edited, *row, *col);
+                let cell = if is_done { " ✓ " } else { " · " };
+
+                let key = (month.clone(), habit.clone(), *day);
+                let mut cell = if self.pending.contains_key(&key) {
+                    style(cell).yellow()
+                } else if is_done {
+                    style(cell).green()
+                } else {
+                    style(cell).dim()
+                };
+                if i == self.row && j == self.col {
+                    cell = cell.reverse();
+                }
+                line.push_str(&cell.to_string());
+            }
+            lines.push(line);
+        }
+
+        lines.push(String::new());
+        lines.push(
+            style("←↑↓→ move · space toggle · [ ] month · s save · q quit")
+                .dim()
+                .to_string(),
+        );
+        lines.push(match self.pending.len() {
+            0 => String::from("No unsaved changes"),
+            n => style(format!("{} unsaved changes", n)).yellow().to_string(),
+        });
+
+        lines
+    }
+
+    fn panel_lines(&self) -> Vec<String> {
+        let (month, _) = &self.months[self.month];
+        let history = get_completion_history(&self.edited, self.year);
+
+        let first_day = format!("{} {} 1", self.year, month);
+        let Ok(first_day) = NaiveDate::parse_from_str(&first_day, "%Y %B %d") else {
+            return Vec::new();
+        };
+        let period = Period::month_of(first_day);
+        let as_of = period.end.min(self.today);
+
+        let mut lines = vec![
+            format!("⚔️  {} EXP: {}", month, period_exp(&history, &period)),
+            String::new(),
+            format!("🔥 Streaks as of {}", as_of.format("%b %-d")),
+        ];
+
+        for (habit, _) in self.habits() {
+            let streak = current_streak(&done_days(&history, Some(&habit)), as_of);
+            let habit = pad_str(&habit, 20, Alignment::Left, Some("…")).to_string();
+            lines.push(format!("  {} {}", habit, streak));
+        }
+
+        lines
+    }
+
+    fn render(&self, term: &Term) -> AppResult<()> {
+        let grid = self.grid_lines();
+        let panel = self.panel_lines();
+        let grid_width = grid
+            .iter()
+            .map(|line| measure_text_width(line))
+            .max()
+            .unwrap_or(0);
+        let (_, term_width) = term.size();
+
+        term.clear_screen()?;
+        if grid_width + PANEL_WIDTH + 4 <= term_width as usize {
+            for i in 0..grid.len().max(panel.len()) {
+                let left = grid.get(i).map_or("", |line| line.as_str());
+                let right = panel.get(i).map_or("", |line| line.as_str());
+                term.write_line(&format!(
+                    "{}    {}",
+                    pad_str(left, grid_width, Alignment::Left, None),
+                    right
+                ))?;
+            }
+        } else {
+            // too narrow for a side panel, show it under the grid
+            for line in grid
+                .iter()
+                .chain([String::new()].iter())
+                .chain(panel.iter())
+            {
+                term.write_line(line)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Full-screen editor for the month grids in `values`. Toggles are kept
+/// locally until saved, then written in one batch per edited month.
+pub async fn run_grid_editor(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    values: &mut Vec<Vec<Value>>,
+    months: &HashMap<String, usize>,
+    wib: &DateTime<Utc>,
+) -> AppResult<()> {
+    let term = Term::stdout();
+    if !term.is_term() {
+        println!("The grid editor needs an interactive terminal.");
+        return Ok(());
+    }
+
+    let mut sorted_months: Vec<(String, usize)> = months
+        .iter()
+        .map(|(month, index)| (month.clone(), *index))
+        .collect();
+    sorted_months.sort_by_key(|(_, index)| *index);
+
+    if sorted_months.is_empty() {
+        println!("No month grids to edit yet.");
+        return Ok(());
+    }
+
+    let edited = values.clone();
+    let mut editor = GridEditor {
+        values: &*values,
+        edited,
+        months: sorted_months,
+        month: 0,
+        row: 0,
+        col: wib.day() as usize - 1,
+        pending: BTreeMap::new(),
+        year: app_config.year,
+        today: wib.date_naive(),
+    };
+    editor.switch_month(0);
+
+    term.hide_cursor()?;
+    let result = edit_loop(&term, &mut editor);
+    term.show_cursor()?;
+    term.clear_screen()?;
+
+    let is_save = result?;
+    let pending = editor.pending;
+
+    if !is_save || pending.is_empty() {
+        if !pending.is_empty() {
+            println!("Discarded {} unsaved changes.", pending.len());
+        }
+        return Ok(());
+    }
+
+    save_pending(hub, app_config, values, &pending).await
+}
+
+/// Returns whether the pending changes should be saved.
+fn edit_loop(term: &Term, editor: &mut GridEditor) -> AppResult<bool> {
+    loop {
+        editor.render(term)?;
+
+        match term.read_key()? {
+            Key::ArrowUp | Key::Char('k') => editor.row = editor.row.saturating_sub(1),
+            Key::ArrowDown | Key::Char('j') => {
+                editor.row = (editor.row + 1).min(editor.habits().len().saturating_sub(1))
+            }
+            Key::ArrowLeft | Key::Char('h') => editor.col = editor.col.saturating_sub(1),
+            Key::ArrowRight | Key::Char('l') => {
+                editor.col = (editor.col + 1).min(editor.dates().len().saturating_sub(1))
+            }
+            Key::Char(' ') | Key::Enter => editor.toggle(),
+            // months are listed newest first, so `[` goes back in time
+            Key::Char('[') if editor.month + 1 < editor.months.len() => {
+                editor.switch_month(editor.month + 1)
+            }
+            Key::Char(']') if editor.month > 0 => editor.switch_month(editor.month - 1),
+            Key::Char('s') => return Ok(true),
+            Key::Char('q') | Key::Escape => {
+                if editor.pending.is_empty() {
+                    return Ok(false);
+                }
+
+                term.show_cursor()?;
+                let mut discard_selector =
+                    select(format!("Discard {} unsaved changes?", editor.pending.len()));
+                discard_selector = discard_selector.item(false, "No, keep editing ✏️", "");
+                discard_selector = discard_selector.item(true, "Yes, discard 🗑️", "");
+                let is_discard = discard_selector.interact()?;
+                term.hide_cursor()?;
+
+                if is_discard {
+                    return Ok(false);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+async fn save_pending(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    values: &mut Vec<Vec<Value>>,
+    pending: &BTreeMap<PendingCell, bool>,
+) -> AppResult<()> {
+    let mut month_targets: BTreeMap<&String, Vec<(String, usize, &'static str)>> = BTreeMap::new();
+    for ((month, habit, day), is_done) in pending {
+        month_targets.entry(month).or_default().push((
+            habit.clone(),
+            *day,
+            if *is_done { "TRUE" } else { "FALSE" },
+        ));
+    }
+
+    for (month, targets) in month_targets {
+        let Some(index) = valid_months(values).get(month).copied() else {
+            continue;
+        };
+        let habits = get_habits(values, index);
+        let dates = get_dates(values, index);
+
+        let mut grid = MonthGrid {
+            values,
+            cur_month: month,
+            habits: &habits,
+            dates: &dates,
+        };
+        let update = PlannedUpdate {
+            description: format!(
+                "Edited {} cells in {} in the grid editor",
+                targets.len(),
+                month
+            ),
+            targets,
+            source: "grid editor",
+        };
+        write_activities(hub, app_config, &mut grid, &update).await?;
+    }
+
+    Ok(())
+}
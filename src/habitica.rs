@@ -0,0 +1,200 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde_json::Value;
+
+use crate::{
+    error::{AppError, AppResult},
+    import::ImportedHistory,
+    interaction::get_user_input_habitica_checklists,
+};
+
+/// Habitica's user data export, as opposed to a plain list of completions.
+pub fn is_habitica_export(json: &Value) -> bool {
+    json["tasks"].is_object()
+}
+
+/// Habitica stores dates as milliseconds since the epoch, or ISO 8601 in older exports.
+fn local_date(value: &Value, utc_offset: Duration) -> Option<NaiveDate> {
+    let time = match value {
+        Value::Number(millis) => DateTime::from_timestamp_millis(millis.as_f64()? as i64)?,
+        Value::String(text) => match text.parse::<i64>() {
+            Ok(millis) => DateTime::from_timestamp_millis(millis)?,
+            Err(_) => DateTime::parse_from_rfc3339(text).ok()?.with_timezone(&Utc),
+        },
+        _ => return None,
+    };
+
+    Some((time + utc_offset).date_naive())
+}
+
+/// Days a task's history shows it done. Entries say so with `completed` for
+/// dailies and `scoredUp` for habits; older exports only have the task value,
+/// which rises whenever the task is scored up. Daily entries are written at
+/// cron, so they count for the day before their timestamp.
+fn done_dates(task: &Value, utc_offset: Duration) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+    let mut previous_value: Option<f64> = None;
+
+    for entry in task["history"].as_array().into_iter().flatten() {
+        let value = entry["value"].as_f64();
+        let (done, is_cron) = match (&entry["completed"], &entry["scoredUp"]) {
+            (Value::Bool(completed), _) => (*completed, true),
+            (_, Value::Number(scored_up)) => (scored_up.as_f64().is_some_and(|n| n > 0.0), false),
+            _ => (
+                matches!((previous_value, value), (Some(before), Some(after)) if after > before),
+                false,
+            ),
+        };
+        previous_value = value.or(previous_value);
+
+        if done && entry["isDue"].as_bool() != Some(false) {
+            let date = local_date(&entry["date"], utc_offset);
+            dates.extend(if is_cron {
+                date.and_then(|date| date.pred_opt())
+            } else {
+                date
+            });
+        }
+    }
+
+    dates
+}
+
+/// Reads dailies and habits from a Habitica user data export. Checklist items
+/// only carry their state since the last cron, so when the user asks for them
+/// as separate habits a checked item counts as done on that day.
+pub fn read_habitica_history(
+    json: &Value,
+    utc_offset: Duration,
+    today: NaiveDate,
+) -> AppResult<ImportedHistory> {
+    let dailies = json["tasks"]["dailys"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let habits = json["tasks"]["habits"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    if dailies.is_empty() && habits.is_empty() {
+        return Err(AppError::Import(String::from(
+            "the Habitica export has no dailies or habits",
+        )));
+    }
+
+    let mut history = ImportedHistory::new();
+    for task in dailies.iter().chain(&habits) {
+        let Some(name) = task["text"].as_str().map(|name| name.trim()) else {
+            continue;
+        };
+        if name.is_empty() {
+            continue;
+        }
+
+        let days = history.entry(name.to_string()).or_default();
+        days.extend(done_dates(task, utc_offset));
+    }
+
+    let has_checklists = dailies.iter().any(|daily| {
+        daily["checklist"]
+            .as_array()
+            .is_some_and(|items| !items.is_empty())
+    });
+    if has_checklists && get_user_input_habitica_checklists()? {
+        let cron_date = local_date(&json["lastCron"], utc_offset).unwrap_or(today);
+
+        for daily in &dailies {
+            let daily_name = daily["text"].as_str().unwrap_or_default().trim();
+            for item in daily["checklist"].as_array().into_iter().flatten() {
+                let Some(item_name) = item["text"].as_str().map(|name| name.trim()) else {
+                    continue;
+                };
+
+                let days = history
+                    .entry(format!("{}: {}", daily_name, item_name))
+                    .or_default();
+                if item["completed"].as_bool() == Some(true) {
+                    days.insert(cron_date);
+                }
+            }
+        }
+    }
+
+    // tasks that were never done don't need a Config row
+    history.retain(|_, days| !days.is_empty());
+
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// 2024-01-01T00:30:00Z
+    const NEW_YEAR_MILLIS: i64 = 1_704_069_000_000;
+    const DAY_MILLIS: i64 = 86_400_000;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn done_dates_reads_completed_dailies() {
+        let task = json!({ "history": [
+            { "date": NEW_YEAR_MILLIS, "value": 1.0, "completed": true, "isDue": true },
+            { "date": NEW_YEAR_MILLIS + DAY_MILLIS, "value": 0.5, "completed": false },
+            { "date": NEW_YEAR_MILLIS + 2 * DAY_MILLIS, "value": 1.5, "completed": true,
+              "isDue": false },
+            { "date": "2024-02-29T12:00:00.000Z", "value": 2.0, "completed": true },
+        ]});
+
+        assert_eq!(
+            done_dates(&task, Duration::zero()),
+            vec![date(2023, 12, 31), date(2024, 2, 28)]
+        );
+    }
+
+    #[test]
+    fn done_dates_reads_scored_up_habits() {
+        let task = json!({ "history": [
+            { "date": NEW_YEAR_MILLIS, "value": 1.0, "scoredUp": 2, "scoredDown": 0 },
+            { "date": NEW_YEAR_MILLIS + DAY_MILLIS, "value": 0.0, "scoredUp": 0,
+              "scoredDown": 1 },
+        ]});
+
+        assert_eq!(done_dates(&task, Duration::zero()), vec![date(2024, 1, 1)]);
+    }
+
+    #[test]
+    fn done_dates_falls_back_to_rising_values() {
+        let task = json!({ "history": [
+            { "date": NEW_YEAR_MILLIS, "value": 0.0 },
+            { "date": NEW_YEAR_MILLIS + DAY_MILLIS, "value": 1.0 },
+            { "date": NEW_YEAR_MILLIS + 2 * DAY_MILLIS, "value": 0.5 },
+            { "date": NEW_YEAR_MILLIS + 3 * DAY_MILLIS },
+            { "date": NEW_YEAR_MILLIS + 4 * DAY_MILLIS, "value": 1.2 },
+        ]});
+
+        assert_eq!(
+            done_dates(&task, Duration::zero()),
+            vec![date(2024, 1, 2), date(2024, 1, 5)]
+        );
+    }
+
+    #[test]
+    fn done_dates_uses_local_day() {
+        let task = json!({ "history": [
+            { "date": NEW_YEAR_MILLIS.to_string(), "completed": true },
+        ]});
+
+        assert_eq!(
+            done_dates(&task, Duration::hours(-5)),
+            vec![date(2023, 12, 30)]
+        );
+        assert_eq!(
+            done_dates(&task, Duration::hours(9)),
+            vec![date(2023, 12, 31)]
+        );
+    }
+}
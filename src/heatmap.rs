@@ -0,0 +1,196 @@
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{Datelike, Duration, NaiveDate};
+use console::{Term, style};
+use serde_json::Value;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    error::AppResult,
+    interaction::{get_user_input_heatmap_habit, get_user_input_heatmap_period},
+    sheet_parser::{CompletionHistory, get_completion_history},
+    stats::{Period, current_streak, done_days},
+};
+
+/// 256-color greens from "nothing done" to "everything done", like GitHub's graph.
+const LEVEL_COLORS: [u8; 5] = [238, 22, 28, 34, 40];
+const WEEKDAY_LABELS: [&str; 7] = ["Mon", "", "Wed", "", "Fri", "", ""];
+const LABEL_WIDTH: usize = 4;
+
+/// Done and tracked habit-days on `date`, counting only `habit` when given.
+/// Days after `today` or without a month grid aren't tracked yet.
+pub fn day_progress(
+    history: &CompletionHistory,
+    habit: Option<&str>,
+    date: NaiveDate,
+    today: NaiveDate,
+) -> Option<(usize, usize)> {
+    if date > today {
+        return None;
+    }
+
+    let (done, total) = history
+        .iter()
+        .filter(|(name, _)| habit.is_none_or(|habit| habit == name.as_str()))
+        .filter_map(|(_, days)| days.get(&date))
+        .fold((0, 0), |(done, total), is_done| {
+            (done + *is_done as usize, total + 1)
+        });
+
+    if total == 0 {
+        None
+    } else {
+        Some((done, total))
+    }
+}
+
+pub fn level(done: usize, total: usize) -> usize {
+    if done == 0 {
+        0
+    } else {
+        ((done * 4).div_ceil(total)).clamp(1, 4)
+    }
+}
+
+/// Renders a GitHub-style contribution graph of `period` for one habit or all
+/// of them, one column per week and one row per weekday.
+pub fn render_heatmap(
+    history: &CompletionHistory,
+    habit: Option<&str>,
+    period: &Period,
+    today: NaiveDate,
+    max_width: usize,
+) -> Vec<String> {
+    let first_monday =
+        period.start - Duration::days(period.start.weekday().num_days_from_monday() as i64);
+    let n_week = ((period.end - first_monday).num_days() / 7 + 1) as usize;
+
+    // fall back to one character per day when two don't fit, e.g. a year in 80 columns
+    let cell_width = if LABEL_WIDTH + n_week * 2 <= max_width {
+        2
+    } else {
+        1
+    };
+
+    let mut month_labels = " ".repeat(LABEL_WIDTH);
+    for week in 0..n_week {
+        let week_start = first_monday + Duration::days(week as i64 * 7);
+        let month_start = (0..7)
+            .map(|day| week_start + Duration::days(day))
+            .find(|date| period.contains(date) && (date.day() == 1 || *date == period.start));
+
+        let column = LABEL_WIDTH + week * cell_width;
+        if let Some(month_start) = month_start
+            && month_labels.width() <= column
+        {
+            month_labels.push_str(&" ".repeat(column - month_labels.width()));
+            month_labels.push_str(&month_start.format("%b").to_string());
+        }
+    }
+
+    let mut lines = vec![month_labels];
+    for (weekday, label) in WEEKDAY_LABELS.iter().enumerate() {
+        let pad = LABEL_WIDTH.saturating_sub(label.width());
+        let mut line = format!("{}{}", label, " ".repeat(pad));
+
+        for week in 0..n_week {
+            let date = first_monday + Duration::days((week * 7 + weekday) as i64);
+            let gap = " ".repeat(cell_width - 1);
+
+            if !period.contains(&date) {
+                line.push_str(&" ".repeat(cell_width));
+                continue;
+            }
+
+            match day_progress(history, habit, date, today) {
+                Some((done, total)) => {
+                    let color = LEVEL_COLORS[level(done, total)];
+                    line.push_str(&style("■").color256(color).to_string());
+                }
+                None => line.push_str(&style("·").dim().to_string()),
+            }
+            line.push_str(&gap);
+        }
+
+        lines.push(line.trim_end().to_string());
+    }
+
+    let mut legend = format!("{}Less ", " ".repeat(LABEL_WIDTH));
+    for color in LEVEL_COLORS {
+        legend.push_str(&style("■").color256(color).to_string());
+        legend.push(' ');
+    }
+    legend.push_str("More");
+    lines.push(String::new());
+    lines.push(legend);
+
+    lines
+}
+
+pub fn print_heatmap(
+    values: &Vec<Vec<Value>>,
+    months: &HashMap<String, usize>,
+    year: i32,
+    today: NaiveDate,
+) -> AppResult<()> {
+    let history = get_completion_history(values, year);
+    if history.is_empty() {
+        println!("No month grids to draw yet. Start a quest first! 🗺️");
+        return Ok(());
+    }
+
+    let habits: BTreeSet<String> = history.keys().cloned().collect();
+    let habit = get_user_input_heatmap_habit(&habits)?;
+    let period = get_user_input_heatmap_period(months, year, today)?;
+
+    let (_, term_width) = Term::stdout().size();
+
+    println!();
+    println!(
+        "🟩 {} · {}",
+        habit.as_deref().unwrap_or("All habits"),
+        period.label
+    );
+    println!();
+    for line in render_heatmap(
+        &history,
+        habit.as_deref(),
+        &period,
+        today,
+        term_width as usize,
+    ) {
+        println!("{}", line);
+    }
+
+    let (done, total) = (0..=(period.end - period.start).num_days())
+        .map(|day| period.start + Duration::days(day))
+        .filter_map(|date| day_progress(&history, habit.as_deref(), date, today))
+        .fold((0, 0), |(done, total), (day_done, day_total)| {
+            (done + day_done, total + day_total)
+        });
+
+    println!();
+    if total == 0 {
+        println!("Nothing tracked in this period yet.");
+    } else {
+        println!(
+            "⚔️  {} of {} habit-days done ({:.0}%)",
+            done,
+            total,
+            done as f64 / total as f64 * 100.0
+        );
+    }
+
+    let as_of = period.end.min(today);
+    if period.start <= as_of {
+        let streak = current_streak(&done_days(&history, habit.as_deref()), as_of);
+        println!(
+            "🔥 Streak as of {}: {} days",
+            as_of.format("%b %-d"),
+            streak
+        );
+    }
+    println!();
+
+    Ok(())
+}
@@ -0,0 +1,586 @@
+use std::path::PathBuf;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use serde_json::Value;
+
+use crate::{
+    AppConfig,
+    error::AppResult,
+    heatmap::{day_progress, level},
+    interaction::{get_user_input_export_path, get_user_input_theme},
+    pixel_font,
+    sheet_parser::{CompletionHistory, get_completion_history},
+    stats::Period,
+};
+
+const MARGIN: u32 = 16;
+const TITLE_HEIGHT: u32 = 28;
+const LABEL_WIDTH: u32 = 32;
+const MONTH_LABEL_HEIGHT: u32 = 16;
+const YEAR_CELL: u32 = 11;
+const YEAR_STEP: u32 = 14;
+const MINI_CELL: u32 = 6;
+const MINI_STEP: u32 = 8;
+const MINI_TITLE_HEIGHT: u32 = 18;
+const SECTION_GAP: u32 = 20;
+const FONT_FAMILY: &str = "-apple-system, Segoe UI, Helvetica, Arial, sans-serif";
+
+pub struct Theme {
+    pub name: &'static str,
+    pub background: &'static str,
+    pub text: &'static str,
+    /// Days after today or without a month grid.
+    pub untracked: &'static str,
+    /// From "nothing done" to "everything done".
+    pub levels: [&'static str; 5],
+}
+
+pub static LIGHT_THEME: Theme = Theme {
+    name: "light",
+    background: "#ffffff",
+    text: "#24292f",
+    untracked: "#f6f8fa",
+    levels: ["#ebedf0", "#9be9a8", "#40c463", "#30a14e", "#216e39"],
+};
+
+pub static DARK_THEME: Theme = Theme {
+    name: "dark",
+    background: "#0d1117",
+    text: "#c9d1d9",
+    untracked: "#0d1117",
+    levels: ["#161b22", "#0e4429", "#006d32", "#26a641", "#39d353"],
+};
+
+struct Rect {
+    x: u32,
+    y: u32,
+    size: u32,
+    color: &'static str,
+}
+
+struct Label {
+    x: u32,
+    y: u32,
+    font_size: u32,
+    text: String,
+}
+
+/// Where a calendar goes and how big its day squares are.
+struct GridLayout {
+    x: u32,
+    y: u32,
+    cell: u32,
+    step: u32,
+}
+
+/// Everything drawn on the image, shared by the SVG and PNG writers.
+struct Drawing {
+    width: u32,
+    height: u32,
+    rects: Vec<Rect>,
+    labels: Vec<Label>,
+}
+
+/// Adds the week-column grid of `period` at `layout` and returns its width.
+fn draw_calendar(
+    drawing: &mut Drawing,
+    history: &CompletionHistory,
+    habit: Option<&str>,
+    period: &Period,
+    today: NaiveDate,
+    theme: &'static Theme,
+    layout: &GridLayout,
+) -> u32 {
+    let first_monday =
+        period.start - Duration::days(period.start.weekday().num_days_from_monday() as i64);
+    let n_week = ((period.end - first_monday).num_days() / 7 + 1) as u32;
+
+    for week in 0..n_week {
+        for weekday in 0..7 {
+            let date = first_monday + Duration::days((week * 7 + weekday) as i64);
+            if !period.contains(&date) {
+                continue;
+            }
+
+            let color = match day_progress(history, habit, date, today) {
+                Some((done, total)) => theme.levels[level(done, total)],
+                None => theme.untracked,
+            };
+
+            drawing.rects.push(Rect {
+                x: layout.x + week * layout.step,
+                y: layout.y + weekday * layout.step,
+                size: layout.cell,
+                color,
+            });
+        }
+    }
+
+    n_week * layout.step
+}
+
+/// A calendar with month and weekday labels, its top-left corner at (`x`, `y`).
+/// Returns its width and height.
+fn draw_labelled_calendar(
+    drawing: &mut Drawing,
+    history: &CompletionHistory,
+    habit: Option<&str>,
+    period: &Period,
+    today: NaiveDate,
+    theme: &'static Theme,
+    (x, y): (u32, u32),
+) -> (u32, u32) {
+    let top = y + MONTH_LABEL_HEIGHT;
+    let left = x + LABEL_WIDTH;
+    let first_monday =
+        period.start - Duration::days(period.start.weekday().num_days_from_monday() as i64);
+
+    let mut month = period.start;
+    while month <= period.end {
+        let week = ((month - first_monday).num_days() / 7) as u32;
+        drawing.labels.push(Label {
+            x: left + week * YEAR_STEP,
+            y: top - 5,
+            font_size: 10,
+            text: month.format("%b").to_string(),
+        });
+        month = Period::month_of(month).end + Duration::days(1);
+    }
+
+    for (weekday, label) in [(0, "Mon"), (2, "Wed"), (4, "Fri")] {
+        drawing.labels.push(Label {
+            x,
+            y: top + weekday * YEAR_STEP + 9,
+            font_size: 10,
+            text: label.to_string(),
+        });
+    }
+
+    let grid_width = draw_calendar(
+        drawing,
+        history,
+        habit,
+        period,
+        today,
+        theme,
+        &GridLayout {
+            x: left,
+            y: top,
+            cell: YEAR_CELL,
+            step: YEAR_STEP,
+        },
+    );
+
+    (LABEL_WIDTH + grid_width, MONTH_LABEL_HEIGHT + 7 * YEAR_STEP)
+}
+
+/// The year heatmap for all habits combined, followed by a mini calendar per habit.
+fn draw_year(
+    history: &CompletionHistory,
+    title: &str,
+    period: &Period,
+    today: NaiveDate,
+    theme: &'static Theme,
+) -> Drawing {
+    let mut drawing = Drawing {
+        width: 0,
+        height: 0,
+        rects: Vec::new(),
+        labels: Vec::new(),
+    };
+
+    drawing.labels.push(Label {
+        x: MARGIN,
+        y: MARGIN + 14,
+        font_size: 16,
+        text: title.to_string(),
+    });
+
+    let (calendar_width, calendar_height) = draw_labelled_calendar(
+        &mut drawing,
+        history,
+        None,
+        period,
+        today,
+        theme,
+        (MARGIN, MARGIN + TITLE_HEIGHT),
+    );
+
+    // legend, right-aligned under the calendar
+    let legend_y = MARGIN + TITLE_HEIGHT + calendar_height + 6;
+    let legend_x = MARGIN + calendar_width - 5 * YEAR_STEP - 28;
+    drawing.labels.push(Label {
+        x: legend_x - 30,
+        y: legend_y + 9,
+        font_size: 10,
+        text: String::from("Less"),
+    });
+    for (i, color) in theme.levels.into_iter().enumerate() {
+        drawing.rects.push(Rect {
+            x: legend_x + i as u32 * YEAR_STEP,
+            y: legend_y,
+            size: YEAR_CELL,
+            color,
+        });
+    }
+    drawing.labels.push(Label {
+        x: legend_x + 5 * YEAR_STEP + 4,
+        y: legend_y + 9,
+        font_size: 10,
+        text: String::from("More"),
+    });
+
+    let mut y = legend_y + YEAR_STEP + SECTION_GAP;
+    let mut width = MARGIN + calendar_width;
+    for habit in history.keys() {
+        drawing.labels.push(Label {
+            x: MARGIN,
+            y: y + 12,
+            font_size: 12,
+            text: habit.clone(),
+        });
+
+        let mini_width = draw_calendar(
+            &mut drawing,
+            history,
+            Some(habit.as_str()),
+            period,
+            today,
+            theme,
+            &GridLayout {
+                x: MARGIN,
+                y: y + MINI_TITLE_HEIGHT,
+                cell: MINI_CELL,
+                step: MINI_STEP,
+            },
+        );
+        width = width.max(MARGIN + mini_width);
+        y += MINI_TITLE_HEIGHT + 7 * MINI_STEP + SECTION_GAP;
+    }
+
+    drawing.width = width + MARGIN;
+    drawing.height = y - SECTION_GAP + MARGIN;
+    drawing
+}
+
+/// A standalone labelled calendar of `period`, for embedding in HTML.
+pub fn calendar_svg(
+    history: &CompletionHistory,
+    habit: Option<&str>,
+    period: &Period,
+    today: NaiveDate,
+    theme: &'static Theme,
+) -> String {
+    let mut drawing = Drawing {
+        width: 0,
+        height: 0,
+        rects: Vec::new(),
+        labels: Vec::new(),
+    };
+
+    let (width, height) =
+        draw_labelled_calendar(&mut drawing, history, habit, period, today, theme, (0, 0));
+    drawing.width = width;
+    drawing.height = height;
+
+    to_svg(&drawing, theme)
+}
+
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn to_svg(drawing: &Drawing, theme: &Theme) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+         viewBox=\"0 0 {w} {h}\">\n",
+        w = drawing.width,
+        h = drawing.height
+    );
+    svg.push_str(&format!(
+        "  <rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+        theme.background
+    ));
+
+    for rect in &drawing.rects {
+        svg.push_str(&format!(
+            "  <rect x=\"{}\" y=\"{}\" width=\"{s}\" height=\"{s}\" rx=\"2\" fill=\"{}\"/>\n",
+            rect.x,
+            rect.y,
+            rect.color,
+            s = rect.size
+        ));
+    }
+
+    svg.push_str(&format!(
+        "  <g font-family=\"{}\" fill=\"{}\">\n",
+        FONT_FAMILY, theme.text
+    ));
+    for label in &drawing.labels {
+        svg.push_str(&format!(
+            "    <text x=\"{}\" y=\"{}\" font-size=\"{}\">{}</text>\n",
+            label.x,
+            label.y,
+            label.font_size,
+            escape_xml(&label.text)
+        ));
+    }
+    svg.push_str("  </g>\n</svg>\n");
+
+    svg
+}
+
+fn parse_hex_color(color: &str) -> [u8; 3] {
+    let channel = |i: usize| {
+        color
+            .get(i..i + 2)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .unwrap_or(0)
+    };
+
+    [channel(1), channel(3), channel(5)]
+}
+
+/// Rasterizes the squares and, in a built-in pixel font, the labels.
+fn to_png(drawing: &Drawing, theme: &Theme) -> Vec<u8> {
+    let (width, height) = (drawing.width as usize, drawing.height as usize);
+    let background = parse_hex_color(theme.background);
+
+    let mut pixels: Vec<u8> = background.repeat(width * height);
+    let mut fill = |x: u32, y: u32, size: u32, color: [u8; 3]| {
+        for y in y as usize..(y + size) as usize {
+            for x in x as usize..(x + size) as usize {
+                if x < width && y < height {
+                    let i = (y * width + x) * 3;
+                    pixels[i..i + 3].copy_from_slice(&color);
+                }
+            }
+        }
+    };
+
+    for rect in &drawing.rects {
+        fill(rect.x, rect.y, rect.size, parse_hex_color(rect.color));
+    }
+
+    let text_color = parse_hex_color(theme.text);
+    for label in &drawing.labels {
+        // pixels are doubled for titles so they stand out like the SVG's larger type
+        let scale = (label.font_size / 8).max(1);
+        let top = label.y.saturating_sub(pixel_font::ASCENT * scale);
+
+        for (i, c) in label.text.chars().enumerate() {
+            let left = label.x + i as u32 * pixel_font::ADVANCE * scale;
+            for (row, bits) in pixel_font::glyph(c).into_iter().enumerate() {
+                for col in 0..5 {
+                    if bits & (0b10000 >> col) != 0 {
+                        fill(
+                            left + col * scale,
+                            top + row as u32 * scale,
+                            scale,
+                            text_color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // every scanline starts with filter type 0 (none)
+    let mut scanlines: Vec<u8> = Vec::with_capacity((width * 3 + 1) * height);
+    for row in pixels.chunks(width * 3) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+
+    let mut header: Vec<u8> = Vec::new();
+    header.extend((width as u32).to_be_bytes());
+    header.extend((height as u32).to_be_bytes());
+    // 8-bit RGB, default compression, filtering and no interlacing
+    header.extend([8, 2, 0, 0, 0]);
+
+    let mut png: Vec<u8> = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+    write_png_chunk(&mut png, b"IHDR", &header);
+    write_png_chunk(
+        &mut png,
+        b"IDAT",
+        &miniz_oxide::deflate::compress_to_vec_zlib(&scanlines, 6),
+    );
+    write_png_chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+fn write_png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    png.extend(kind);
+    png.extend(data);
+
+    let mut crc_input = kind.to_vec();
+    crc_input.extend(data);
+    png.extend(crc32(&crc_input).to_be_bytes());
+}
+
+/// CRC-32 (ISO 3309), as required for PNG chunks.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(0xffffffff, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+/// Writes `<path>.svg` and `<path>.png` with the tracked year's heatmap and a
+/// mini calendar per habit, read from the year tab's month grids.
+pub async fn export_heatmap_images(
+    values: &Vec<Vec<Value>>,
+    app_config: &AppConfig,
+    today: NaiveDate,
+) -> AppResult<()> {
+    let history = get_completion_history(values, app_config.year);
+    if history.is_empty() {
+        println!("No month grids to draw yet. Start a quest first! 🗺️");
+        return Ok(());
+    }
+
+    let theme = get_user_input_theme()?;
+    let default_path = format!("habit-heatmap-{}-{}", app_config.year, theme.name);
+    let path = PathBuf::from(get_user_input_export_path(&default_path)?);
+
+    let owner = app_config
+        .user
+        .as_deref()
+        .unwrap_or(&app_config.profile_name);
+    let title = format!("{} · {}", owner, app_config.year);
+    let period = Period::year_of(NaiveDate::from_ymd_opt(app_config.year, 1, 1).unwrap());
+    let drawing = draw_year(&history, &title, &period, today, theme);
+
+    let svg_path = path.with_extension("svg");
+    let png_path = path.with_extension("png");
+    tokio::fs::write(&svg_path, to_svg(&drawing, theme)).await?;
+    tokio::fs::write(&png_path, to_png(&drawing, theme)).await?;
+
+    println!(
+        "🖼️  Saved {} and {}",
+        svg_path.display(),
+        png_path.display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_be_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    fn drawing() -> Drawing {
+        Drawing {
+            width: 40,
+            height: 20,
+            rects: vec![Rect {
+                x: 2,
+                y: 3,
+                size: 4,
+                color: "#216e39",
+            }],
+            labels: vec![Label {
+                x: 10,
+                y: 12,
+                font_size: 10,
+                text: String::from("I"),
+            }],
+        }
+    }
+
+    /// The RGB pixels of a PNG written by `to_png`, one `width * 3` row each.
+    fn decode_pixels(png: &[u8]) -> (usize, Vec<Vec<u8>>) {
+        let width = u32_at(png, 16) as usize;
+        // IHDR is 25 bytes after the signature, IDAT follows it
+        let idat_len = u32_at(png, 33) as usize;
+        assert_eq!(&png[37..41], b"IDAT");
+        let scanlines =
+            miniz_oxide::inflate::decompress_to_vec_zlib(&png[41..41 + idat_len]).unwrap();
+
+        let rows = scanlines
+            .chunks(width * 3 + 1)
+            .map(|line| {
+                assert_eq!(line[0], 0);
+                line[1..].to_vec()
+            })
+            .collect();
+        (width, rows)
+    }
+
+    fn pixel(rows: &[Vec<u8>], x: usize, y: usize) -> [u8; 3] {
+        rows[y][x * 3..x * 3 + 3].try_into().unwrap()
+    }
+
+    #[test]
+    fn crc32_matches_known_values() {
+        assert_eq!(crc32(b"IEND"), 0xae426082);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn write_png_chunk_lays_out_length_type_data_crc() {
+        let mut png = Vec::new();
+        write_png_chunk(&mut png, b"tEXt", b"abc");
+
+        assert_eq!(u32_at(&png, 0), 3);
+        assert_eq!(&png[4..8], b"tEXt");
+        assert_eq!(&png[8..11], b"abc");
+        assert_eq!(u32_at(&png, 11), crc32(b"tEXtabc"));
+        assert_eq!(png.len(), 15);
+    }
+
+    #[test]
+    fn to_png_writes_header_and_end() {
+        let png = to_png(&drawing(), &LIGHT_THEME);
+
+        assert_eq!(&png[..8], &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a]);
+        assert_eq!(u32_at(&png, 8), 13);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!((u32_at(&png, 16), u32_at(&png, 20)), (40, 20));
+        assert_eq!(&png[24..29], &[8, 2, 0, 0, 0]);
+        assert_eq!(
+            &png[png.len() - 12..],
+            &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+        );
+    }
+
+    #[test]
+    fn to_png_draws_squares_and_labels() {
+        let (width, rows) = decode_pixels(&to_png(&drawing(), &LIGHT_THEME));
+
+        assert_eq!((width, rows.len()), (40, 20));
+        assert_eq!(pixel(&rows, 0, 0), [0xff, 0xff, 0xff]);
+        assert_eq!(pixel(&rows, 2, 3), [0x21, 0x6e, 0x39]);
+        assert_eq!(pixel(&rows, 5, 6), [0x21, 0x6e, 0x39]);
+        assert_eq!(pixel(&rows, 6, 6), [0xff, 0xff, 0xff]);
+        // the "I" stands on the baseline at y = 12: its stem is column 2 of the glyph
+        assert_eq!(pixel(&rows, 12, 5), [0x24, 0x29, 0x2f]);
+        assert_eq!(pixel(&rows, 12, 11), [0x24, 0x29, 0x2f]);
+        assert_eq!(pixel(&rows, 12, 12), [0xff, 0xff, 0xff]);
+        assert_eq!(pixel(&rows, 10, 8), [0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn escape_xml_escapes_quotes_and_markup() {
+        assert_eq!(
+            escape_xml(r#"Tom & Jerry's <"run">"#),
+            "Tom &amp; Jerry&apos;s &lt;&quot;run&quot;&gt;"
+        );
+    }
+}
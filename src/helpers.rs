@@ -2,11 +2,14 @@ use google_sheets4::{
     FieldMask,
     api::{
         AddSheetRequest, AutoResizeDimensionsRequest, BooleanCondition, CellData, CellFormat,
-        Color, DataValidationRule, DeleteDimensionRequest, DimensionRange, GridProperties,
-        GridRange, InsertDimensionRequest, RepeatCellRequest, Request, SetDataValidationRequest,
-        SheetProperties, TextFormat,
+        Color, DataValidationRule, DeleteDimensionRequest, DimensionRange, ExtendedValue,
+        GridCoordinate, GridProperties, GridRange, InsertDimensionRequest, RepeatCellRequest,
+        Request, RowData, SetDataValidationRequest, SheetProperties, TextFormat,
+        UpdateCellsRequest,
     },
 };
+use serde_json::Value;
+use unicode_width::UnicodeWidthStr;
 
 /// Helper to build a Color
 pub fn color(r: f32, g: f32, b: f32) -> Color {
@@ -490,7 +493,13 @@ pub fn set_data_validation_request(
     }
 }
 
-pub fn add_sheet_request(title: &str, index: Option<i32>, rows: i32, cols: i32) -> Request {
+pub fn add_sheet_request(
+    title: &str,
+    sheet_id: Option<i32>,
+    index: Option<i32>,
+    rows: i32,
+    cols: i32,
+) -> Request {
     let grid_properties = GridProperties {
         row_count: Some(rows),
         column_count: Some(cols),
@@ -511,7 +520,7 @@ pub fn add_sheet_request(title: &str, index: Option<i32>, rows: i32, cols: i32)
                 tab_color_style: None,
                 right_to_left: None,
                 hidden: None,
-                sheet_id: None,
+                sheet_id,
                 sheet_type: None,
                 data_source_sheet_properties: None,
             }),
@@ -752,6 +761,145 @@ pub fn delete_rows(sheet_id: i32, dimension: String, start_index: i32, end_index
     }
 }
 
+/// Helper to build an UpdateCells Request writing `rows` starting at (`start_row`, `start_col`).
+/// "TRUE"/"FALSE" become booleans and numeric strings become numbers, like USER_ENTERED.
+pub fn update_cells_request(
+    sheet_id: i32,
+    start_row: i32,
+    start_col: i32,
+    rows: &[Vec<Value>],
+) -> Request {
+    let rows: Vec<RowData> = rows
+        .iter()
+        .map(|row| RowData {
+            values: Some(row.iter().map(cell_data).collect()),
+        })
+        .collect();
+
+    Request {
+        update_cells: Some(UpdateCellsRequest {
+            rows: Some(rows),
+            fields: Some(FieldMask::new(&["userEnteredValue".to_string()])),
+            start: Some(GridCoordinate {
+                sheet_id: Some(sheet_id),
+                row_index: Some(start_row),
+                column_index: Some(start_col),
+            }),
+            range: None,
+        }),
+
+        // Everything else left uninitialized (non-recursive)
+        add_sheet: None,
+        add_banding: None,
+        add_chart: None,
+        add_conditional_format_rule: None,
+        add_data_source: None,
+        add_dimension_group: None,
+        add_filter_view: None,
+        add_named_range: None,
+        add_protected_range: None,
+        append_cells: None,
+        append_dimension: None,
+        auto_fill: None,
+        auto_resize_dimensions: None,
+        clear_basic_filter: None,
+        copy_paste: None,
+        create_developer_metadata: None,
+        cut_paste: None,
+        delete_banding: None,
+        delete_conditional_format_rule: None,
+        delete_data_source: None,
+        delete_developer_metadata: None,
+        delete_dimension: None,
+        delete_dimension_group: None,
+        delete_duplicates: None,
+        delete_embedded_object: None,
+        delete_filter_view: None,
+        delete_named_range: None,
+        delete_protected_range: None,
+        delete_range: None,
+        delete_sheet: None,
+        duplicate_filter_view: None,
+        duplicate_sheet: None,
+        find_replace: None,
+        insert_dimension: None,
+        insert_range: None,
+        merge_cells: None,
+        move_dimension: None,
+        paste_data: None,
+        randomize_range: None,
+        refresh_data_source: None,
+        repeat_cell: None,
+        set_basic_filter: None,
+        set_data_validation: None,
+        sort_range: None,
+        text_to_columns: None,
+        trim_whitespace: None,
+        unmerge_cells: None,
+        update_banding: None,
+        update_borders: None,
+        update_chart_spec: None,
+        update_conditional_format_rule: None,
+        update_data_source: None,
+        update_developer_metadata: None,
+        update_dimension_group: None,
+        update_dimension_properties: None,
+        update_embedded_object_border: None,
+        update_embedded_object_position: None,
+        update_filter_view: None,
+        update_named_range: None,
+        update_protected_range: None,
+        update_sheet_properties: None,
+        update_slicer_spec: None,
+        update_spreadsheet_properties: None,
+        cancel_data_source_refresh: None,
+        add_slicer: None,
+    }
+}
+
+fn cell_data(value: &Value) -> CellData {
+    let text = value
+        .as_str()
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| value.to_string());
+
+    let user_entered_value = if text.is_empty() {
+        None
+    } else if text == "TRUE" || text == "FALSE" {
+        Some(ExtendedValue {
+            bool_value: Some(text == "TRUE"),
+            error_value: None,
+            formula_value: None,
+            number_value: None,
+            string_value: None,
+        })
+    } else {
+        let number_value = text.parse::<f64>().ok();
+        Some(ExtendedValue {
+            bool_value: None,
+            error_value: None,
+            formula_value: None,
+            number_value,
+            string_value: number_value.is_none().then_some(text),
+        })
+    };
+
+    CellData {
+        user_entered_value,
+        effective_value: None,
+        effective_format: None,
+        pivot_table: None,
+        data_source_table: None,
+        data_source_formula: None,
+        text_format_runs: None,
+        hyperlink: None,
+        note: None,
+        user_entered_format: None,
+        data_validation: None,
+        formatted_value: None,
+    }
+}
+
 pub fn group_consecutive(values: &[usize]) -> Vec<Vec<usize>> {
     if values.is_empty() {
         return vec![];
@@ -774,3 +922,25 @@ pub fn group_consecutive(values: &[usize]) -> Vec<Vec<usize>> {
 
     groups
 }
+
+pub fn print_table(rows: &[Vec<String>]) {
+    let column_count = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..column_count)
+        .map(|i| {
+            rows.iter()
+                .filter_map(|row| row.get(i))
+                .map(|cell| cell.width())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    for row in rows {
+        let mut line = String::from(" ");
+        for (cell, width) in row.iter().zip(&widths) {
+            let pad = width.saturating_sub(cell.width());
+            line.push_str(&format!(" {}{} ", cell, " ".repeat(pad)));
+        }
+        println!("{}", line.trim_end());
+    }
+}
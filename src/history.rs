@@ -0,0 +1,363 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use cliclack::select;
+use google_sheets4::{
+    Sheets,
+    api::{BatchUpdateValuesRequest, ValueRange},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use yup_oauth2::{hyper::client::HttpConnector, hyper_rustls::HttpsConnector};
+
+use crate::{
+    AppConfig,
+    audit_log::{append_audit_log, split_range},
+    cache::clear_cached_sheet,
+    data_updater::{get_cell_address, quote_sheet_name},
+    dry_run::{CellChange, print_dry_run},
+    error::{AppError, AppResult},
+    executor::{RetryPolicy, execute},
+    helpers::print_table,
+    init::{history_file_path, valid_months},
+    sheet_parser::{config_table, get_dates, get_habits},
+};
+
+/// Older sessions are dropped so the history file stays small.
+const MAX_SESSIONS: usize = 20;
+
+/// A cell a session wrote, kept by habit and date rather than by address, as
+/// month grids and habit rows shift when a month starts or history is imported.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SessionCell {
+    pub sheet_name: String,
+    pub habit: String,
+    /// `YYYY-MM-DD` in a month grid, empty for the habit's IsActive flag on Config.
+    pub date: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// One committed batch of cell writes, newest last in the history file.
+#[derive(Serialize, Deserialize)]
+pub struct UpdateSession {
+    pub spreadsheet_id: String,
+    pub description: String,
+    /// Unix timestamp.
+    pub committed_at: i64,
+    #[serde(default)]
+    pub cells: Vec<SessionCell>,
+}
+
+async fn read_history(app_config: &AppConfig) -> AppResult<Vec<UpdateSession>> {
+    let history_path = history_file_path(&app_config.profile_name);
+    if !history_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = tokio::fs::read_to_string(&history_path).await?;
+    let mut sessions: Vec<UpdateSession> = serde_json::from_str(&content).map_err(|err| {
+        AppError::Config(format!("failed to parse {}: {err}", history_path.display()))
+    })?;
+    // sessions saved with cell addresses only can't be placed in the sheet reliably
+    sessions.retain(|session| !session.cells.is_empty());
+
+    Ok(sessions)
+}
+
+async fn write_history(app_config: &AppConfig, sessions: &[UpdateSession]) -> AppResult<()> {
+    let content = serde_json::to_string_pretty(sessions)
+        .map_err(|err| AppError::Config(format!("failed to serialize history: {err}")))?;
+    tokio::fs::write(history_file_path(&app_config.profile_name), content).await?;
+
+    Ok(())
+}
+
+/// Appends a committed batch so it can be undone later. The cells are already
+/// written by now, so failing to save the history only warns.
+pub async fn record_session(app_config: &AppConfig, description: String, changes: Vec<CellChange>) {
+    if changes.is_empty() {
+        return;
+    }
+
+    let cells: Vec<SessionCell> = changes
+        .into_iter()
+        .filter(|change| !change.habit.is_empty())
+        .map(|change| SessionCell {
+            sheet_name: split_range(&change.range).0,
+            habit: change.habit,
+            date: change.date,
+            old: change.old,
+            new: change.new,
+        })
+        .collect();
+
+    if let Err(err) = append_session(app_config, description, cells).await {
+        eprintln!("⚠️  Couldn't save undo history: {err}");
+    }
+}
+
+async fn append_session(
+    app_config: &AppConfig,
+    description: String,
+    cells: Vec<SessionCell>,
+) -> AppResult<()> {
+    let mut sessions = read_history(app_config).await?;
+    sessions.push(UpdateSession {
+        spreadsheet_id: app_config.spreadsheet_id.clone(),
+        description,
+        committed_at: Utc::now().timestamp(),
+        cells,
+    });
+
+    if sessions.len() > MAX_SESSIONS {
+        sessions.drain(..sessions.len() - MAX_SESSIONS);
+    }
+
+    write_history(app_config, &sessions).await
+}
+
+/// Where `cell` sits in its sheet's `values` right now, as a zero-based
+/// (row, column).
+fn locate_cell(cell: &SessionCell, values: &Vec<Vec<Value>>) -> Option<(usize, usize)> {
+    if cell.date.is_empty() {
+        let row = *get_habits(values, config_table::START_ROW_INDEX).get(&cell.habit)?;
+        return Some((
+            row,
+            config_table::Column::IsActive.as_usize_zero_based_index(),
+        ));
+    }
+
+    let date = NaiveDate::parse_from_str(&cell.date, "%Y-%m-%d").ok()?;
+    let index = *valid_months(values).get(&date.format("%B").to_string())?;
+    let row = *get_habits(values, index).get(&cell.habit)?;
+    let col = *get_dates(values, index).get(&(date.day() as usize))?;
+
+    Some((row, col))
+}
+
+/// Restores the previous values of the last recorded session for this
+/// spreadsheet, unless any of its cells were changed again since. Cells are
+/// found by habit and date in the sheet as it is now.
+pub async fn undo_last_session(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+) -> AppResult<()> {
+    let mut sessions = read_history(app_config).await?;
+    let Some(position) = sessions
+        .iter()
+        .rposition(|session| session.spreadsheet_id == app_config.spreadsheet_id)
+    else {
+        println!("Nothing to undo. Your scroll of history is empty 📜");
+        return Ok(());
+    };
+
+    let session = &sessions[position];
+
+    let mut sheets: BTreeMap<&String, Vec<Vec<Value>>> = session
+        .cells
+        .iter()
+        .map(|cell| (&cell.sheet_name, Vec::new()))
+        .collect();
+    let mut request = hub
+        .spreadsheets()
+        .values_batch_get(&app_config.spreadsheet_id);
+    for sheet_name in sheets.keys() {
+        request = request.add_ranges(&quote_sheet_name(sheet_name));
+    }
+
+    let mut retry_policy = RetryPolicy::default();
+    let response = execute(request.delegate(&mut retry_policy).doit()).await?;
+    for (values, value_range) in sheets
+        .values_mut()
+        .zip(response.value_ranges.unwrap_or_default())
+    {
+        *values = value_range.values.unwrap_or_default();
+    }
+
+    let mut reverted: Vec<CellChange> = Vec::new();
+    let mut changed_since: Vec<String> = Vec::new();
+    for cell in &session.cells {
+        let subject = if cell.date.is_empty() {
+            format!("{} (IsActive)", cell.habit)
+        } else {
+            format!("{} on {}", cell.habit, cell.date)
+        };
+
+        let values = &sheets[&cell.sheet_name];
+        let Some((row, col)) = locate_cell(cell, values) else {
+            changed_since.push(format!("{} is no longer in '{}'", subject, cell.sheet_name));
+            continue;
+        };
+
+        let current = values
+            .get(row)
+            .and_then(|cells| cells.get(col))
+            .and_then(|value| value.as_str())
+            .unwrap_or_default();
+        if current != cell.new {
+            changed_since.push(format!("{} is now {:?}", subject, current));
+            continue;
+        }
+
+        reverted.push(CellChange {
+            range: format!(
+                "{}!{}",
+                quote_sheet_name(&cell.sheet_name),
+                get_cell_address(row + 1, col + 1)
+            ),
+            habit: cell.habit.clone(),
+            date: cell.date.clone(),
+            old: cell.new.clone(),
+            new: cell.old.clone(),
+        });
+    }
+
+    if !changed_since.is_empty() {
+        println!(
+            "⛔ Can't undo \"{}\", these cells were changed since:\n  {}",
+            session.description,
+            changed_since.join("\n  ")
+        );
+        return Ok(());
+    }
+
+    let committed_at = DateTime::from_timestamp(session.committed_at, 0)
+        .map(|time| {
+            (time + app_config.utc_offset)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_default();
+
+    if app_config.dry_run {
+        print_dry_run(&reverted, &[], &Default::default());
+        return Ok(());
+    }
+
+    println!();
+    println!("↩️  {} ({})", session.description, committed_at);
+    let mut rows: Vec<Vec<String>> = vec![vec![
+        String::from("Habit"),
+        String::from("Date"),
+        String::from("Now"),
+        String::from("Restore"),
+    ]];
+    for change in &reverted {
+        rows.push(vec![
+            change.habit.clone(),
+            change.date.clone(),
+            change.old.clone(),
+            change.new.clone(),
+        ]);
+    }
+    print_table(&rows);
+    println!();
+    let mut is_undo_selector = select("Restore these cells?");
+    is_undo_selector = is_undo_selector.item(true, "yes", "");
+    is_undo_selector = is_undo_selector.item(false, "no", "");
+
+    if !is_undo_selector.interact()? {
+        return Ok(());
+    }
+
+    let data: Vec<ValueRange> = reverted
+        .iter()
+        .map(|change| ValueRange {
+            range: Some(change.range.clone()),
+            values: Some(vec![vec![Value::String(change.new.clone())]]),
+            ..Default::default()
+        })
+        .collect();
+
+    let batch = BatchUpdateValuesRequest {
+        value_input_option: Some("USER_ENTERED".to_string()),
+        data: Some(data),
+        ..Default::default()
+    };
+
+    let response = execute(
+        hub.spreadsheets()
+            .values_batch_update(batch, &app_config.spreadsheet_id)
+            .delegate(&mut RetryPolicy::default())
+            .doit(),
+    )
+    .await?;
+
+    append_audit_log(hub, app_config, "undo", &reverted).await;
+    clear_cached_sheet(app_config);
+    sessions.remove(position);
+    write_history(app_config, &sessions).await?;
+
+    println!(
+        "{} cells restored. Time rewinds, hero ⏳",
+        response.total_updated_cells.unwrap_or(0)
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn rows(rows: &[&[&str]]) -> Vec<Vec<Value>> {
+        rows.iter()
+            .map(|row| row.iter().map(|cell| json!(cell)).collect())
+            .collect()
+    }
+
+    fn cell(habit: &str, date: &str) -> SessionCell {
+        SessionCell {
+            sheet_name: String::from("2024"),
+            habit: habit.to_string(),
+            date: date.to_string(),
+            old: String::from("FALSE"),
+            new: String::from("TRUE"),
+        }
+    }
+
+    #[test]
+    fn locate_cell_follows_grids_that_moved() {
+        // February's grid was inserted above March after the session was saved
+        let values = rows(&[
+            &["February", "1", "2", "3"],
+            &["Read", "FALSE", "FALSE", "TRUE"],
+            &[],
+            &["March", "1", "2", "3"],
+            &["Run", "TRUE", "FALSE", "FALSE"],
+            &["Read", "FALSE", "TRUE", "FALSE"],
+        ]);
+
+        assert_eq!(
+            locate_cell(&cell("Read", "2024-03-02"), &values),
+            Some((5, 2))
+        );
+        assert_eq!(
+            locate_cell(&cell("Read", "2024-02-03"), &values),
+            Some((1, 3))
+        );
+    }
+
+    #[test]
+    fn locate_cell_misses_removed_habits_and_days() {
+        let values = rows(&[&["March", "1", "2"], &["Read", "FALSE", "TRUE"]]);
+
+        assert_eq!(locate_cell(&cell("Run", "2024-03-01"), &values), None);
+        assert_eq!(locate_cell(&cell("Read", "2024-03-05"), &values), None);
+        assert_eq!(locate_cell(&cell("Read", "2024-04-01"), &values), None);
+    }
+
+    #[test]
+    fn locate_cell_finds_config_flags() {
+        let values = rows(&[
+            &["Habit", "IsComplete", "IsActive"],
+            &["Read", "FALSE", "TRUE"],
+            &["Run", "FALSE", "FALSE"],
+        ]);
+
+        assert_eq!(locate_cell(&cell("Run", ""), &values), Some((2, 2)));
+    }
+}
@@ -0,0 +1,470 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use chrono::{Datelike, NaiveDate};
+use google_sheets4::Sheets;
+use yup_oauth2::{hyper::client::HttpConnector, hyper_rustls::HttpsConnector};
+
+use crate::{
+    AppConfig,
+    data_updater::quote_sheet_name,
+    error::AppResult,
+    executor::{RetryPolicy, execute},
+    heatmap_export::{LIGHT_THEME, calendar_svg, escape_xml},
+    sheet_parser::{CompletionHistory, get_completion_history, get_sheet_titles},
+    stats::{Period, current_streak, done_days, longest_streak, merge_history},
+};
+
+const STYLE: &str = "
+body { font-family: -apple-system, Segoe UI, Helvetica, Arial, sans-serif; color: #24292f;
+       max-width: 960px; margin: 2rem auto; padding: 0 1rem; }
+a { color: #0969da; text-decoration: none; }
+a:hover { text-decoration: underline; }
+.muted { color: #57606a; }
+.cards { display: flex; flex-wrap: wrap; gap: 1rem; margin: 1.5rem 0; }
+.card { border: 1px solid #d0d7de; border-radius: 6px; padding: 0.75rem 1rem; min-width: 9rem; }
+.card b { display: block; font-size: 1.5rem; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: 0.4rem 0.6rem; border-bottom: 1px solid #d0d7de; }
+.calendar { overflow-x: auto; margin-bottom: 1rem; }
+.bars { display: grid; grid-template-columns: 6rem 1fr 4rem; gap: 0.3rem 0.6rem;
+        align-items: center; }
+.bar { background: #ebedf0; border-radius: 3px; height: 0.8rem; }
+.bar span { display: block; background: #40c463; border-radius: 3px; height: 100%; }
+";
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Year tabs of this profile as `(year, sheet name)`, oldest first.
+pub async fn get_year_sheets(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+) -> AppResult<Vec<(i32, String)>> {
+    let sheet_titles = get_sheet_titles(hub, app_config).await?;
+
+    let mut year_sheets: Vec<(i32, String)> = sheet_titles
+        .iter()
+        .filter_map(|title| {
+            let year = match &app_config.user {
+                Some(user) => title.strip_suffix(&format!("-{user}"))?,
+                None => title.as_str(),
+            };
+            Some((year.parse::<i32>().ok()?, title.clone()))
+        })
+        .collect();
+    year_sheets.sort();
+
+    Ok(year_sheets)
+}
+
+/// Reads every year tab of this profile and merges their month grids.
+pub async fn get_year_histories(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+) -> AppResult<CompletionHistory> {
+    let year_sheets = get_year_sheets(hub, app_config).await?;
+
+    let mut history = CompletionHistory::new();
+    if year_sheets.is_empty() {
+        return Ok(history);
+    }
+
+    let mut retry_policy = RetryPolicy::default();
+    let mut request = hub
+        .spreadsheets()
+        .values_batch_get(&app_config.spreadsheet_id)
+        .delegate(&mut retry_policy);
+    for (_, sheet_name) in &year_sheets {
+        request = request.add_ranges(&quote_sheet_name(sheet_name));
+    }
+
+    let response = execute(request.doit()).await?;
+    for ((year, _), value_range) in year_sheets
+        .into_iter()
+        .zip(response.value_ranges.unwrap_or_default())
+    {
+        let values = value_range.values.unwrap_or_default();
+        merge_history(&mut history, get_completion_history(&values, year));
+    }
+
+    Ok(history)
+}
+
+/// Done and tracked habit-days up to `today`, for `habit` or all habits.
+fn tracked_days(
+    history: &CompletionHistory,
+    habit: Option<&str>,
+    today: NaiveDate,
+) -> (usize, usize) {
+    history
+        .iter()
+        .filter(|(name, _)| habit.is_none_or(|habit| habit == name.as_str()))
+        .flat_map(|(_, days)| days.iter())
+        .filter(|(date, _)| **date <= today)
+        .fold((0, 0), |(done, total), (_, is_done)| {
+            (done + *is_done as usize, total + 1)
+        })
+}
+
+fn weekday_breakdown(
+    history: &CompletionHistory,
+    habit: Option<&str>,
+    today: NaiveDate,
+) -> [(usize, usize); 7] {
+    let mut breakdown = [(0, 0); 7];
+    for (_, days) in history
+        .iter()
+        .filter(|(name, _)| habit.is_none_or(|habit| habit == name.as_str()))
+    {
+        for (date, is_done) in days.iter().filter(|(date, _)| **date <= today) {
+            let (done, total) = &mut breakdown[date.weekday().num_days_from_monday() as usize];
+            *done += *is_done as usize;
+            *total += 1;
+        }
+    }
+
+    breakdown
+}
+
+/// EXP earned per month, keyed by the first day of the month.
+fn monthly_exp(history: &CompletionHistory, habit: Option<&str>) -> BTreeMap<NaiveDate, usize> {
+    let mut exp: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    for (_, days) in history
+        .iter()
+        .filter(|(name, _)| habit.is_none_or(|habit| habit == name.as_str()))
+    {
+        for (date, is_done) in days {
+            let month = date.with_day(1).unwrap();
+            *exp.entry(month).or_insert(0) += *is_done as usize;
+        }
+    }
+
+    exp
+}
+
+fn percent(done: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        done as f64 / total as f64 * 100.0
+    }
+}
+
+fn cards(cards: &[(&str, String)]) -> String {
+    let mut html = String::from("<div class=\"cards\">\n");
+    for (label, value) in cards {
+        html.push_str(&format!(
+            "  <div class=\"card\"><b>{}</b><span class=\"muted\">{}</span></div>\n",
+            escape_xml(value),
+            label
+        ));
+    }
+    html.push_str("</div>\n");
+    html
+}
+
+/// Horizontal bars of `(label, fraction of the longest bar, value shown)`.
+fn bars(rows: &[(String, f64, String)]) -> String {
+    let mut html = String::from("<div class=\"bars\">\n");
+    for (label, fraction, value) in rows {
+        html.push_str(&format!(
+            "  <span>{}</span><div class=\"bar\"><span style=\"width: {:.1}%\"></span></div>\
+             <span class=\"muted\">{}</span>\n",
+            escape_xml(label),
+            fraction.clamp(0.0, 1.0) * 100.0,
+            escape_xml(value)
+        ));
+    }
+    html.push_str("</div>\n");
+    html
+}
+
+fn calendars(history: &CompletionHistory, habit: Option<&str>, today: NaiveDate) -> String {
+    let years: BTreeSet<i32> = history
+        .values()
+        .flat_map(|days| days.keys())
+        .map(|date| date.year())
+        .collect();
+
+    let mut html = String::new();
+    for year in years.iter().rev() {
+        let period = Period::year_of(NaiveDate::from_ymd_opt(*year, 1, 1).unwrap());
+        html.push_str(&format!(
+            "<h3>{}</h3>\n<div class=\"calendar\">{}</div>\n",
+            year,
+            calendar_svg(history, habit, &period, today, &LIGHT_THEME)
+        ));
+    }
+    html
+}
+
+fn breakdown_sections(
+    history: &CompletionHistory,
+    habit: Option<&str>,
+    today: NaiveDate,
+) -> String {
+    let weekday_rows: Vec<(String, f64, String)> = weekday_breakdown(history, habit, today)
+        .iter()
+        .zip(WEEKDAYS)
+        .map(|((done, total), weekday)| {
+            let rate = percent(*done, *total);
+            (weekday.to_string(), rate / 100.0, format!("{:.0}%", rate))
+        })
+        .collect();
+
+    let exp = monthly_exp(history, habit);
+    let max_exp = exp.values().copied().max().unwrap_or(0).max(1);
+    let month_rows: Vec<(String, f64, String)> = exp
+        .iter()
+        .map(|(month, exp)| {
+            (
+                month.format("%b %Y").to_string(),
+                *exp as f64 / max_exp as f64,
+                format!("{} EXP", exp),
+            )
+        })
+        .collect();
+
+    format!(
+        "<h2>By weekday</h2>\n{}<h2>EXP per month</h2>\n{}",
+        bars(&weekday_rows),
+        bars(&month_rows)
+    )
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!doctype html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_xml(title),
+        STYLE,
+        body
+    )
+}
+
+/// File names for each habit's page, made unique when two habits slug the same.
+fn habit_pages(history: &CompletionHistory) -> BTreeMap<String, String> {
+    let mut used: BTreeSet<String> = BTreeSet::new();
+    let mut pages: BTreeMap<String, String> = BTreeMap::new();
+
+    for habit in history.keys() {
+        let slug: String = habit
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '-' })
+            .collect();
+        let slug = slug.trim_matches('-').to_string();
+        let slug = if slug.is_empty() {
+            String::from("habit")
+        } else {
+            slug
+        };
+
+        let mut file_name = format!("{}.html", slug);
+        let mut n = 2;
+        while used.contains(&file_name) {
+            file_name = format!("{}-{}.html", slug, n);
+            n += 1;
+        }
+
+        used.insert(file_name.clone());
+        pages.insert(habit.clone(), file_name);
+    }
+
+    pages
+}
+
+/// The index table row linking to a habit's page.
+fn habit_row(
+    history: &CompletionHistory,
+    habit: &str,
+    file_name: &str,
+    today: NaiveDate,
+) -> String {
+    let (done, total) = tracked_days(history, Some(habit), today);
+    let days = done_days(history, Some(habit));
+    format!(
+        "<tr><td><a href=\"habits/{}\">{}</a></td><td>{:.0}%</td><td>{}</td>\
+         <td>{}</td><td>{}</td></tr>\n",
+        escape_xml(file_name),
+        escape_xml(habit),
+        percent(done, total),
+        current_streak(&days, today),
+        longest_streak(&days),
+        done
+    )
+}
+
+fn habit_page(history: &CompletionHistory, habit: &str, today: NaiveDate) -> String {
+    let (done, total) = tracked_days(history, Some(habit), today);
+    let days = done_days(history, Some(habit));
+
+    let mut body = format!(
+        "<p><a href=\"../index.html\">← All habits</a></p>\n<h1>{}</h1>\n",
+        escape_xml(habit)
+    );
+    body.push_str(&cards(&[
+        ("completion", format!("{:.0}%", percent(done, total))),
+        (
+            "current streak",
+            format!("{} days", current_streak(&days, today)),
+        ),
+        ("longest streak", format!("{} days", longest_streak(&days))),
+        ("total EXP", done.to_string()),
+    ]));
+    body.push_str(&calendars(history, Some(habit), today));
+    body.push_str(&breakdown_sections(history, Some(habit), today));
+
+    page(habit, &body)
+}
+
+/// Writes a self-contained static site to `dir`: an index with overall
+/// completion and a page per habit, built from every year tab of this profile.
+pub async fn generate_html_report(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    dir: &Path,
+    today: NaiveDate,
+) -> AppResult<()> {
+    let history = get_year_histories(hub, app_config).await?;
+    if history.is_empty() {
+        println!("No year tabs with month grids found, nothing to report yet.");
+        return Ok(());
+    }
+
+    let owner = app_config
+        .user
+        .as_deref()
+        .unwrap_or(&app_config.profile_name);
+    let pages = habit_pages(&history);
+
+    tokio::fs::create_dir_all(dir.join("habits")).await?;
+
+    let (done, total) = tracked_days(&history, None, today);
+    let mut body = format!(
+        "<h1>{}'s habit report</h1>\n<p class=\"muted\">Generated on {}</p>\n",
+        escape_xml(owner),
+        today.format("%B %-d, %Y")
+    );
+    body.push_str(&cards(&[
+        ("completion", format!("{:.0}%", percent(done, total))),
+        ("total EXP", done.to_string()),
+        ("habits", history.len().to_string()),
+    ]));
+
+    body.push_str(
+        "<h2>Habits</h2>\n<table>\n<tr><th>Habit</th><th>Completion</th>\
+         <th>Current streak</th><th>Longest streak</th><th>EXP</th></tr>\n",
+    );
+    for (habit, file_name) in &pages {
+        body.push_str(&habit_row(&history, habit, file_name, today));
+    }
+    body.push_str("</table>\n<h2>All habits</h2>\n");
+    body.push_str(&calendars(&history, None, today));
+    body.push_str(&breakdown_sections(&history, None, today));
+
+    tokio::fs::write(dir.join("index.html"), page("Habit report", &body)).await?;
+
+    for (habit, file_name) in &pages {
+        let html = habit_page(&history, habit, today);
+        tokio::fs::write(dir.join("habits").join(file_name), html).await?;
+    }
+
+    println!(
+        "📚 Report with {} habit pages written to {}",
+        pages.len(),
+        dir.join("index.html").display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn history(habits: &[(&str, &[(NaiveDate, bool)])]) -> CompletionHistory {
+        habits
+            .iter()
+            .map(|(habit, days)| (habit.to_string(), days.iter().copied().collect()))
+            .collect()
+    }
+
+    #[test]
+    fn habit_pages_gives_each_habit_its_own_file() {
+        let history = history(&[
+            ("Read", &[]),
+            ("read!", &[]),
+            ("Drink water", &[]),
+            ("🏃", &[]),
+        ]);
+
+        let pages = habit_pages(&history);
+
+        assert_eq!(pages.len(), 4);
+        assert_eq!(pages["Drink water"], "drink-water.html");
+        assert_eq!(pages["Read"], "read.html");
+        assert_eq!(pages["read!"], "read-2.html");
+        assert_eq!(pages["🏃"], "habit.html");
+    }
+
+    #[test]
+    fn habit_names_are_escaped_in_titles_and_links() {
+        let habit = "<b>Tea & \"cake\"</b>";
+        let history = history(&[(habit, &[(date(2024, 3, 1), true)])]);
+        let pages = habit_pages(&history);
+        let today = date(2024, 3, 1);
+
+        assert_eq!(pages[habit], "b-tea----cake---b.html");
+        let row = habit_row(&history, habit, &pages[habit], today);
+        assert!(row.contains(
+            "<a href=\"habits/b-tea----cake---b.html\">\
+             &lt;b&gt;Tea &amp; &quot;cake&quot;&lt;/b&gt;</a>"
+        ));
+
+        let html = habit_page(&history, habit, today);
+        assert!(html.contains("<title>&lt;b&gt;Tea &amp; &quot;cake&quot;&lt;/b&gt;</title>"));
+        assert!(html.contains("<h1>&lt;b&gt;Tea &amp; &quot;cake&quot;&lt;/b&gt;</h1>"));
+        assert!(!html.contains(habit));
+    }
+
+    #[test]
+    fn weekday_breakdown_counts_tracked_days_up_to_today() {
+        // 2024-03-04 is a Monday.
+        let history = history(&[
+            (
+                "Read",
+                &[
+                    (date(2024, 3, 4), true),
+                    (date(2024, 3, 5), false),
+                    (date(2024, 3, 11), true),
+                    (date(2024, 3, 18), true),
+                ],
+            ),
+            (
+                "Run",
+                &[(date(2024, 3, 4), false), (date(2024, 3, 10), true)],
+            ),
+        ]);
+        let today = date(2024, 3, 11);
+
+        let all = weekday_breakdown(&history, None, today);
+        assert_eq!(
+            all,
+            [(2, 3), (0, 1), (0, 0), (0, 0), (0, 0), (0, 0), (1, 1)]
+        );
+
+        let read = weekday_breakdown(&history, Some("Read"), today);
+        assert_eq!(
+            read,
+            [(2, 2), (0, 1), (0, 0), (0, 0), (0, 0), (0, 0), (0, 0)]
+        );
+    }
+}
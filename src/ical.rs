@@ -0,0 +1,772 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Utc, Weekday};
+
+use crate::{
+    AppConfig,
+    cache::checksum,
+    error::{AppError, AppResult},
+    export::ExportRow,
+    import::ImportedHistory,
+};
+
+/// Prefix of the per-completion event titles, so exported calendars import back.
+const DONE_PREFIX: &str = "✅ ";
+/// RFC 5545 lines are folded at 75 octets.
+const MAX_LINE_OCTETS: usize = 75;
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n' | 'N')) => {
+                unescaped.push('\n');
+                chars.next();
+            }
+            ('\\', Some(escaped)) => {
+                unescaped.push(escaped);
+                chars.next();
+            }
+            (c, _) => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Appends `line` folded onto continuation lines, never splitting a character.
+fn push_line(ics: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            ics.push_str("\r\n ");
+            octets = 1;
+        }
+        ics.push(c);
+        octets += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn push_event(ics: &mut String, uid: &str, stamp: &str, date: NaiveDate, lines: &[String]) {
+    push_line(ics, "BEGIN:VEVENT");
+    push_line(ics, &format!("UID:{}", uid));
+    push_line(ics, &format!("DTSTAMP:{}", stamp));
+    push_line(
+        ics,
+        &format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")),
+    );
+    push_line(
+        ics,
+        &format!(
+            "DTEND;VALUE=DATE:{}",
+            (date + Duration::days(1)).format("%Y%m%d")
+        ),
+    );
+    for line in lines {
+        push_line(ics, line);
+    }
+    push_line(ics, "END:VEVENT");
+}
+
+/// An iCalendar file with an all-day event per completion in `rows`, or with
+/// `daily_summary` one event per tracked day up to `today` listing what was done.
+pub fn to_ics(
+    rows: &[ExportRow],
+    daily_summary: bool,
+    today: NaiveDate,
+    app_config: &AppConfig,
+) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    // UIDs stay the same between exports so calendar apps update instead of duplicating
+    let uid = |date: NaiveDate, subject: &str| {
+        let key = format!("{}/{}", app_config.profile_name, subject);
+        format!(
+            "{}-{:016x}@habit-tracker",
+            date.format("%Y%m%d"),
+            checksum(key.as_bytes())
+        )
+    };
+
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//habit_tracker//Habit completions//EN");
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    push_line(
+        &mut ics,
+        &format!("X-WR-CALNAME:Habits ({})", app_config.profile_name),
+    );
+
+    let mut days: BTreeMap<NaiveDate, Vec<&ExportRow>> = BTreeMap::new();
+    for row in rows {
+        if let Ok(date) = NaiveDate::parse_from_str(&row.date, "%Y-%m-%d") {
+            days.entry(date).or_default().push(row);
+        }
+    }
+
+    for (date, day_rows) in days.range(..=today) {
+        if daily_summary {
+            let done: Vec<&str> = day_rows
+                .iter()
+                .filter(|row| row.value)
+                .map(|row| row.habit.as_str())
+                .collect();
+            let missed: Vec<&str> = day_rows
+                .iter()
+                .filter(|row| !row.value)
+                .map(|row| row.habit.as_str())
+                .collect();
+
+            let mut description = format!("Done: {}", done.join(", "));
+            if !missed.is_empty() {
+                description.push_str(&format!("\nMissed: {}", missed.join(", ")));
+            }
+
+            push_event(
+                &mut ics,
+                &uid(*date, "day"),
+                &stamp,
+                *date,
+                &[
+                    format!(
+                        "SUMMARY:{}",
+                        escape_text(&format!("🎯 {}/{} habits", done.len(), day_rows.len()))
+                    ),
+                    format!("DESCRIPTION:{}", escape_text(&description)),
+                    String::from("TRANSP:TRANSPARENT"),
+                ],
+            );
+            continue;
+        }
+
+        for row in day_rows.iter().filter(|row| row.value) {
+            let mut lines = vec![
+                format!(
+                    "SUMMARY:{}",
+                    escape_text(&format!("{}{}", DONE_PREFIX, row.habit))
+                ),
+                String::from("TRANSP:TRANSPARENT"),
+            ];
+            if let Some(note) = &row.note {
+                lines.push(format!("DESCRIPTION:{}", escape_text(note)));
+            }
+
+            push_event(&mut ics, &uid(*date, &row.habit), &stamp, *date, &lines);
+        }
+    }
+
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+/// Case-insensitive match of `text` against `|`-separated alternatives where
+/// `*` matches any run of characters and `?` any single one.
+fn matches_pattern(pattern: &str, text: &str) -> bool {
+    fn glob(pattern: &[char], text: &[char]) -> bool {
+        match pattern.split_first() {
+            None => text.is_empty(),
+            Some(('*', rest)) => (0..=text.len()).any(|skip| glob(rest, &text[skip..])),
+            Some((c, rest)) => text
+                .split_first()
+                .is_some_and(|(t, text_rest)| (*c == '?' || c == t) && glob(rest, text_rest)),
+        }
+    }
+
+    let text: Vec<char> = text.trim().to_lowercase().chars().collect();
+    pattern.split('|').any(|alternative| {
+        let alternative: Vec<char> = alternative.trim().to_lowercase().chars().collect();
+        glob(&alternative, &text)
+    })
+}
+
+#[derive(Default)]
+struct Event {
+    uid: Option<String>,
+    summary: Option<String>,
+    date: Option<NaiveDate>,
+    is_cancelled: bool,
+    rule: Option<String>,
+    /// `RDATE`s, occurrences on top of the rule.
+    added: Vec<NaiveDate>,
+    /// `EXDATE`s, occurrences the rule skips.
+    excluded: BTreeSet<NaiveDate>,
+    /// Set on an event that replaces one occurrence of the recurring event
+    /// with the same `UID`.
+    recurrence_id: Option<NaiveDate>,
+}
+
+/// Local date an event starts on. UTC times are shifted by the profile's
+/// offset; dates, floating times and `TZID` times are taken as written.
+fn event_date(value: &str, utc_offset: Duration) -> Option<NaiveDate> {
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((time + utc_offset).date());
+    }
+
+    NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()
+}
+
+fn event_dates(value: &str, utc_offset: Duration) -> Vec<NaiveDate> {
+    value
+        .split(',')
+        .filter_map(|value| event_date(value, utc_offset))
+        .collect()
+}
+
+fn parse_weekday(day: &str) -> Option<Weekday> {
+    match day.trim().to_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Days an event starting on `start` repeats on under `rule`, up to `today`.
+/// Handles `FREQ`, `INTERVAL`, `COUNT`, `UNTIL` and plain weekly `BYDAY`;
+/// `None` for anything else, like "the last Friday of the month".
+fn recurrence_dates(
+    start: NaiveDate,
+    rule: &str,
+    utc_offset: Duration,
+    today: NaiveDate,
+) -> Option<Vec<NaiveDate>> {
+    let mut freq = None;
+    let mut interval = 1;
+    let mut count = None;
+    let mut until = None;
+    let mut weekdays = Vec::new();
+    for part in rule.split(';') {
+        let (name, value) = part.split_once('=')?;
+        match name.trim().to_uppercase().as_str() {
+            "FREQ" => freq = Some(value.trim().to_uppercase()),
+            "INTERVAL" => interval = value.trim().parse::<i64>().ok().filter(|n| *n > 0)?,
+            "COUNT" => count = Some(value.trim().parse::<usize>().ok()?),
+            "UNTIL" => until = Some(event_date(value.trim(), utc_offset)?),
+            "BYDAY" => {
+                for day in value.split(',') {
+                    weekdays.push(parse_weekday(day)?);
+                }
+            }
+            "WKST" => {}
+            _ => return None,
+        }
+    }
+    let freq = freq?;
+    if !weekdays.is_empty() && freq != "WEEKLY" {
+        return None;
+    }
+    weekdays.sort_by_key(|day| day.num_days_from_monday());
+
+    let last = until.map_or(today, |until| until.min(today));
+    let week_start = start - Duration::days(start.weekday().num_days_from_monday() as i64);
+
+    // DTSTART is always the first occurrence
+    let mut dates = vec![start];
+    for period in 0.. {
+        let step = period * interval;
+        // the first day of the period, and the occurrences in it
+        let (period_start, occurrences) = match freq.as_str() {
+            "DAILY" => {
+                let date = start + Duration::days(step);
+                (date, vec![date])
+            }
+            "WEEKLY" if weekdays.is_empty() => {
+                let date = start + Duration::weeks(step);
+                (date, vec![date])
+            }
+            "WEEKLY" => {
+                let monday = week_start + Duration::weeks(step);
+                let days = weekdays
+                    .iter()
+                    .map(|day| monday + Duration::days(day.num_days_from_monday() as i64))
+                    .collect();
+                (monday, days)
+            }
+            "MONTHLY" => {
+                let months = start.month0() as i64 + step;
+                let (year, month) = (
+                    start.year() + (months / 12) as i32,
+                    (months % 12) as u32 + 1,
+                );
+                // months without the start's day, like February 30th, are skipped
+                let date = NaiveDate::from_ymd_opt(year, month, start.day());
+                (
+                    NaiveDate::from_ymd_opt(year, month, 1)?,
+                    date.into_iter().collect(),
+                )
+            }
+            "YEARLY" => {
+                let year = start.year() + step as i32;
+                let date = NaiveDate::from_ymd_opt(year, start.month(), start.day());
+                (
+                    NaiveDate::from_ymd_opt(year, 1, 1)?,
+                    date.into_iter().collect(),
+                )
+            }
+            _ => return None,
+        };
+        if period_start > last {
+            break;
+        }
+
+        for date in occurrences.into_iter().filter(|date| *date > start) {
+            if count.is_some_and(|count| dates.len() >= count) {
+                return Some(dates);
+            }
+            if date <= last {
+                dates.push(date);
+            }
+        }
+    }
+
+    Some(dates)
+}
+
+/// The top-level events of an unfolded `.ics` file. Properties of components
+/// nested in an event, like a `VALARM`'s `SUMMARY`, are left out.
+fn read_events(lines: &[String], utc_offset: Duration) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut components: Vec<String> = Vec::new();
+    let mut event: Option<Event> = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let property = name.split(';').next().unwrap_or_default().to_uppercase();
+        let value = value.trim_end();
+
+        if property == "BEGIN" {
+            if value.eq_ignore_ascii_case("VEVENT") {
+                event = Some(Event::default());
+            }
+            components.push(value.to_uppercase());
+            continue;
+        }
+        if property == "END" {
+            components.pop();
+            if value.eq_ignore_ascii_case("VEVENT") {
+                events.extend(event.take());
+            }
+            continue;
+        }
+
+        let (Some(event), Some("VEVENT")) = (event.as_mut(), components.last().map(String::as_str))
+        else {
+            continue;
+        };
+        match property.as_str() {
+            "UID" => event.uid = Some(value.to_string()),
+            "SUMMARY" => event.summary = Some(unescape_text(value)),
+            "DTSTART" => event.date = event_date(value, utc_offset),
+            "STATUS" => event.is_cancelled = value == "CANCELLED",
+            "RRULE" => event.rule = Some(value.to_string()),
+            "RDATE" => event.added.extend(event_dates(value, utc_offset)),
+            "EXDATE" => event.excluded.extend(event_dates(value, utc_offset)),
+            "RECURRENCE-ID" => event.recurrence_id = event_date(value, utc_offset),
+            _ => {}
+        }
+    }
+
+    events
+}
+
+/// Reads the events of an `.ics` file as completions. Titles matching a
+/// profile's `calendar_patterns` mark that habit, and `✅ <habit>` titles from
+/// our own export mark the habit they name. Recurring events count every
+/// occurrence up to `today`, except those with rules too intricate to follow,
+/// which only count their first occurrence and `RDATE`s.
+pub fn read_ics_history(
+    text: &str,
+    app_config: &AppConfig,
+    today: NaiveDate,
+) -> AppResult<ImportedHistory> {
+    // unfold continuation lines first, they start with a space or tab
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let events = read_events(&lines, app_config.utc_offset);
+    // occurrences that were moved or cancelled on their own
+    let overridden: BTreeSet<(&String, NaiveDate)> = events
+        .iter()
+        .filter_map(|event| Some((event.uid.as_ref()?, event.recurrence_id?)))
+        .collect();
+
+    let mut history = ImportedHistory::new();
+    let mut n_partial = 0;
+    for event in &events {
+        let (Some(summary), Some(start), false) = (&event.summary, event.date, event.is_cancelled)
+        else {
+            continue;
+        };
+
+        let mut dates = match &event.rule {
+            None => vec![start],
+            Some(rule) => recurrence_dates(start, rule, app_config.utc_offset, today)
+                .unwrap_or_else(|| {
+                    n_partial += 1;
+                    vec![start]
+                }),
+        };
+        dates.extend(&event.added);
+        dates.retain(|date| {
+            !event.excluded.contains(date)
+                && (event.recurrence_id.is_some()
+                    || event
+                        .uid
+                        .as_ref()
+                        .is_none_or(|uid| !overridden.contains(&(uid, *date))))
+        });
+
+        let mut habits: Vec<String> = app_config
+            .calendar_patterns
+            .iter()
+            .filter(|(_, pattern)| matches_pattern(pattern, summary))
+            .map(|(habit, _)| habit.clone())
+            .collect();
+        if let Some(habit) = summary.strip_prefix(DONE_PREFIX) {
+            habits.push(habit.trim().to_string());
+        }
+
+        for habit in habits {
+            history.entry(habit).or_default().extend(&dates);
+        }
+    }
+
+    if events.is_empty() {
+        return Err(AppError::Import(String::from("the calendar has no events")));
+    }
+    if history.is_empty() {
+        return Err(AppError::Import(format!(
+            "none of the {} events matched a habit, add title patterns under \
+             calendar_patterns in profile '{}'",
+            events.len(),
+            app_config.profile_name
+        )));
+    }
+    if n_partial > 0 {
+        println!(
+            "⚠️  {} recurring events repeat in ways the import can't follow, only their \
+             first occurrence and extra dates were read.",
+            n_partial
+        );
+    }
+
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::init::AuthMode;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn app_config(patterns: &[(&str, &str)]) -> AppConfig {
+        AppConfig {
+            spreadsheet_id: String::new(),
+            year: 2024,
+            sheet_name: String::from("2024"),
+            config_sheet_name: String::from("Config"),
+            audit_sheet_name: String::from("Audit"),
+            auth_mode: AuthMode::default(),
+            profile_name: String::from("default"),
+            credentials_path: PathBuf::new(),
+            utc_offset: Duration::hours(7),
+            user: None,
+            team_members: Vec::new(),
+            leaderboard_profiles: Vec::new(),
+            calendar_patterns: patterns
+                .iter()
+                .map(|(habit, pattern)| (habit.to_string(), pattern.to_string()))
+                .collect(),
+            dry_run: false,
+        }
+    }
+
+    fn ics(events: &str) -> String {
+        format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n{events}END:VCALENDAR\r\n")
+    }
+
+    fn done(history: &ImportedHistory, habit: &str) -> Vec<NaiveDate> {
+        history
+            .get(habit)
+            .map(|days| days.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn push_line_folds_at_75_octets() {
+        let mut ics = String::new();
+        push_line(&mut ics, &format!("SUMMARY:{}", "a".repeat(100)));
+
+        let lines: Vec<&str> = ics.trim_end().split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 75);
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(lines[1].len(), 1 + 108 - 75);
+    }
+
+    #[test]
+    fn push_line_never_splits_a_character() {
+        let mut ics = String::new();
+        push_line(&mut ics, &format!("SUMMARY:{}", "✅".repeat(30)));
+
+        for line in ics.split("\r\n") {
+            assert!(line.len() <= MAX_LINE_OCTETS);
+        }
+        let unfolded = ics.replace("\r\n ", "");
+        assert_eq!(unfolded, format!("SUMMARY:{}\r\n", "✅".repeat(30)));
+    }
+
+    #[test]
+    fn unescape_text_reverses_escape_text() {
+        let text = "Read; then, write\\notes\nand rest";
+
+        assert_eq!(unescape_text(&escape_text(text)), text);
+        assert_eq!(unescape_text("a\\Nb\\,c\\\\"), "a\nb,c\\");
+        assert_eq!(unescape_text("trailing\\"), "trailing\\");
+    }
+
+    #[test]
+    fn matches_pattern_globs_alternatives() {
+        assert!(matches_pattern("gym*|*run*", "Gym: legs"));
+        assert!(matches_pattern("gym*|*run*", "Morning RUN"));
+        assert!(matches_pattern("yoga ?", "Yoga 2"));
+        assert!(!matches_pattern("yoga ?", "Yoga 12"));
+        assert!(!matches_pattern("gym", "Gym: legs"));
+    }
+
+    #[test]
+    fn event_date_shifts_utc_times() {
+        let offset = Duration::hours(7);
+
+        assert_eq!(
+            event_date("20231231T180000Z", offset),
+            Some(date(2024, 1, 1))
+        );
+        assert_eq!(
+            event_date("20240101T030000Z", -offset),
+            Some(date(2023, 12, 31))
+        );
+        assert_eq!(
+            event_date("20240228T200000Z", offset),
+            Some(date(2024, 2, 29))
+        );
+        assert_eq!(
+            event_date("20230228T200000Z", offset),
+            Some(date(2023, 3, 1))
+        );
+    }
+
+    #[test]
+    fn event_date_takes_local_times_as_written() {
+        let offset = Duration::hours(7);
+
+        assert_eq!(event_date("20240229", offset), Some(date(2024, 2, 29)));
+        assert_eq!(
+            event_date("20241231T230000", offset),
+            Some(date(2024, 12, 31))
+        );
+        assert_eq!(event_date("20230229", offset), None);
+        assert_eq!(event_date("soon", offset), None);
+    }
+
+    #[test]
+    fn recurrence_dates_stops_at_count_until_and_today() {
+        let start = date(2023, 12, 30);
+        let today = date(2024, 1, 10);
+        let offset = Duration::zero();
+
+        assert_eq!(
+            recurrence_dates(start, "FREQ=DAILY;COUNT=3", offset, today),
+            Some(vec![
+                date(2023, 12, 30),
+                date(2023, 12, 31),
+                date(2024, 1, 1)
+            ])
+        );
+        assert_eq!(
+            recurrence_dates(
+                start,
+                "FREQ=DAILY;INTERVAL=2;UNTIL=20240103T235959Z",
+                offset,
+                today
+            ),
+            Some(vec![date(2023, 12, 30), date(2024, 1, 1), date(2024, 1, 3)])
+        );
+        assert_eq!(
+            recurrence_dates(start, "FREQ=WEEKLY", offset, today).map(|dates| dates.len()),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn recurrence_dates_follows_weekly_byday() {
+        // a Wednesday
+        let start = date(2024, 2, 28);
+
+        assert_eq!(
+            recurrence_dates(
+                start,
+                "FREQ=WEEKLY;BYDAY=MO,WE,FR",
+                Duration::zero(),
+                date(2024, 3, 6)
+            ),
+            Some(vec![
+                date(2024, 2, 28),
+                date(2024, 3, 1),
+                date(2024, 3, 4),
+                date(2024, 3, 6),
+            ])
+        );
+    }
+
+    #[test]
+    fn recurrence_dates_skips_missing_days() {
+        let offset = Duration::zero();
+
+        assert_eq!(
+            recurrence_dates(date(2024, 1, 31), "FREQ=MONTHLY", offset, date(2024, 5, 31)),
+            Some(vec![
+                date(2024, 1, 31),
+                date(2024, 3, 31),
+                date(2024, 5, 31)
+            ])
+        );
+        assert_eq!(
+            recurrence_dates(date(2020, 2, 29), "FREQ=YEARLY", offset, date(2024, 3, 1)),
+            Some(vec![date(2020, 2, 29), date(2024, 2, 29)])
+        );
+    }
+
+    #[test]
+    fn recurrence_dates_gives_up_on_intricate_rules() {
+        let offset = Duration::zero();
+        let today = date(2024, 6, 1);
+
+        assert_eq!(
+            recurrence_dates(date(2024, 1, 26), "FREQ=MONTHLY;BYDAY=-1FR", offset, today),
+            None
+        );
+        assert_eq!(
+            recurrence_dates(
+                date(2024, 1, 1),
+                "FREQ=MONTHLY;BYMONTHDAY=1,15",
+                offset,
+                today
+            ),
+            None
+        );
+        assert_eq!(
+            recurrence_dates(date(2024, 1, 1), "FREQ=HOURLY", offset, today),
+            None
+        );
+    }
+
+    #[test]
+    fn read_ics_history_ignores_alarm_summaries() {
+        let text = ics("BEGIN:VEVENT\r\n\
+                        SUMMARY:Morning run\r\n\
+                        DTSTART;VALUE=DATE:20240229\r\n\
+                        BEGIN:VALARM\r\n\
+                        ACTION:DISPLAY\r\n\
+                        SUMMARY:Reminder\r\n\
+                        END:VALARM\r\n\
+                        END:VEVENT\r\n");
+        let history = read_ics_history(
+            &text,
+            &app_config(&[("Run", "*run*"), ("Remind", "reminder")]),
+            date(2024, 3, 1),
+        )
+        .unwrap();
+
+        assert_eq!(done(&history, "Run"), vec![date(2024, 2, 29)]);
+        assert!(!history.contains_key("Remind"));
+    }
+
+    #[test]
+    fn read_ics_history_unfolds_and_reads_own_export() {
+        let text = ics("BEGIN:VEVENT\r\n\
+                        SUMMARY:✅ Read a \r\n \
+                        book\r\n\
+                        DTSTART:20231231T200000Z\r\n\
+                        END:VEVENT\r\n");
+        let history = read_ics_history(&text, &app_config(&[]), date(2024, 1, 1)).unwrap();
+
+        assert_eq!(done(&history, "Read a book"), vec![date(2024, 1, 1)]);
+    }
+
+    #[test]
+    fn read_ics_history_expands_recurring_events() {
+        let text = ics("BEGIN:VEVENT\r\n\
+                        UID:gym@example.com\r\n\
+                        SUMMARY:Gym\r\n\
+                        DTSTART;VALUE=DATE:20231229\r\n\
+                        RRULE:FREQ=DAILY\r\n\
+                        EXDATE;VALUE=DATE:20231231\r\n\
+                        RDATE;VALUE=DATE:20231201,20231215\r\n\
+                        END:VEVENT\r\n\
+                        BEGIN:VEVENT\r\n\
+                        UID:gym@example.com\r\n\
+                        RECURRENCE-ID;VALUE=DATE:20240101\r\n\
+                        SUMMARY:Gym\r\n\
+                        STATUS:CANCELLED\r\n\
+                        DTSTART;VALUE=DATE:20240101\r\n\
+                        END:VEVENT\r\n\
+                        BEGIN:VEVENT\r\n\
+                        UID:gym@example.com\r\n\
+                        RECURRENCE-ID;VALUE=DATE:20240102\r\n\
+                        SUMMARY:Gym\r\n\
+                        DTSTART;VALUE=DATE:20240104\r\n\
+                        END:VEVENT\r\n");
+        let history =
+            read_ics_history(&text, &app_config(&[("Gym", "gym")]), date(2024, 1, 3)).unwrap();
+
+        assert_eq!(
+            done(&history, "Gym"),
+            vec![
+                date(2023, 12, 1),
+                date(2023, 12, 15),
+                date(2023, 12, 29),
+                date(2023, 12, 30),
+                date(2024, 1, 3),
+                date(2024, 1, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_ics_history_needs_matching_events() {
+        let no_events = read_ics_history(&ics(""), &app_config(&[]), date(2024, 1, 1));
+        let no_match = read_ics_history(
+            &ics("BEGIN:VEVENT\r\nSUMMARY:Dentist\r\nDTSTART:20240101\r\nEND:VEVENT\r\n"),
+            &app_config(&[("Gym", "gym")]),
+            date(2024, 1, 1),
+        );
+
+        assert!(matches!(no_events, Err(AppError::Import(_))));
+        assert!(matches!(no_match, Err(AppError::Import(_))));
+    }
+}
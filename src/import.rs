@@ -0,0 +1,762 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::Path,
+    str::FromStr,
+};
+
+use chrono::{Datelike, NaiveDate};
+use google_sheets4::{Sheets, api::BatchUpdateValuesRequest, api::ValueRange};
+use serde_json::Value;
+use time::Month;
+use yup_oauth2::{hyper::client::HttpConnector, hyper_rustls::HttpsConnector};
+
+use crate::{
+    AppConfig,
+    audit_log::append_audit_log,
+    cache::clear_cached_sheet,
+    data_updater::{get_cell_address, quote_sheet_name, set_cell_value, set_data},
+    dry_run::{CellChange, print_dry_run},
+    error::{AppError, AppResult},
+    executor::{RetryPolicy, execute},
+    habitica::{is_habitica_export, read_habitica_history},
+    helpers::{add_sheet_request, print_table},
+    history::record_session,
+    html_report::get_year_sheets,
+    ical::read_ics_history,
+    init::{user_sheet_name, valid_months},
+    interaction::{get_user_input_confirm_import, get_user_input_habit_mapping},
+    sheet_parser::{
+        config_table, get_active_habits, get_dates, get_habits, get_sheet_ids, next_sheet_id,
+        remember_sheet_id,
+    },
+    template_builder::{
+        config_habit_requests, habit_rows_requests, send_batch_update, template_grid_requests,
+    },
+};
+
+/// Cells per `values_batch_update` call, well under the API's request size limit.
+const IMPORT_BATCH_SIZE: usize = 1000;
+
+/// Days each habit was done, as read from another app's export.
+pub type ImportedHistory = BTreeMap<String, BTreeSet<NaiveDate>>;
+
+/// Where an imported habit's completions are written.
+pub enum HabitMapping {
+    Create,
+    Existing(String),
+    Skip,
+}
+
+/// `YYYY-MM-DD`, ignoring any time that follows.
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text.trim().get(..10)?, "%Y-%m-%d").ok()
+}
+
+fn is_done(value: &str) -> bool {
+    matches!(
+        value.trim().to_lowercase().as_str(),
+        "1" | "2" | "true" | "yes" | "y" | "x" | "done" | "✓" | "✔" | "✅"
+    )
+}
+
+/// Splits CSV text into records, honouring quoted fields with commas,
+/// doubled quotes and line breaks.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', _) => in_quotes = !in_quotes,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    records
+        .into_iter()
+        .filter(|record| record.iter().any(|field| !field.trim().is_empty()))
+        .collect()
+}
+
+/// Reads either long rows of `date,habit[,value]`, like our own CSV export, or
+/// a wide table with a `Date` column and one column per habit, like Loop
+/// Habit Tracker's `Checkmarks.csv`.
+fn read_csv_history(text: &str) -> AppResult<ImportedHistory> {
+    let records = parse_csv(text);
+    let Some((header, rows)) = records.split_first() else {
+        return Err(AppError::Import(String::from("the CSV file is empty")));
+    };
+
+    let header: Vec<String> = header
+        .iter()
+        .map(|name| name.trim().to_lowercase())
+        .collect();
+    let column = |name: &str| header.iter().position(|column| column == name);
+
+    let mut history = ImportedHistory::new();
+    match (column("date"), column("habit")) {
+        (Some(date_col), Some(habit_col)) => {
+            let value_col = column("value");
+            for row in rows {
+                let (Some(date), Some(habit)) = (
+                    row.get(date_col).and_then(|date| parse_date(date)),
+                    row.get(habit_col).map(|habit| habit.trim()),
+                ) else {
+                    continue;
+                };
+                // without a value column every row is a completion
+                let done = value_col.is_none_or(|col| row.get(col).is_some_and(|v| is_done(v)));
+                if done && !habit.is_empty() {
+                    history.entry(habit.to_string()).or_default().insert(date);
+                }
+            }
+        }
+        (Some(date_col), None) => {
+            // Loop writes 2 for a check and 1 for days its schedule skipped, so
+            // 1 only counts as done in files that never use 2
+            let is_loop = rows.iter().flatten().any(|value| value.trim() == "2");
+            let names = records[0].clone();
+
+            for row in rows {
+                let Some(date) = row.get(date_col).and_then(|date| parse_date(date)) else {
+                    continue;
+                };
+                for (col, value) in row.iter().enumerate() {
+                    let habit = names.get(col).map_or("", |name| name.trim());
+                    if col == date_col || habit.is_empty() {
+                        continue;
+                    }
+                    let done = if is_loop {
+                        value.trim() == "2"
+                    } else {
+                        is_done(value)
+                    };
+                    if done {
+                        history.entry(habit.to_string()).or_default().insert(date);
+                    }
+                }
+            }
+        }
+        _ => {
+            return Err(AppError::Import(String::from(
+                "the CSV file needs a 'date' column and either a 'habit' column or one \
+                 column per habit",
+            )));
+        }
+    }
+
+    Ok(history)
+}
+
+/// Reads a list of `{ "date", "habit", "value" }` objects, like our own JSON export.
+fn read_json_history(json: &Value) -> AppResult<ImportedHistory> {
+    let entries = json.as_array().ok_or_else(|| {
+        AppError::Import(String::from(
+            "expected a list of {date, habit, value} entries",
+        ))
+    })?;
+
+    let mut history = ImportedHistory::new();
+    for entry in entries {
+        let Some(date) = entry["date"].as_str().and_then(parse_date) else {
+            continue;
+        };
+        let Some(habit) = entry["habit"].as_str().map(|habit| habit.trim()) else {
+            continue;
+        };
+
+        let done = match &entry["value"] {
+            Value::Null => true,
+            Value::Bool(done) => *done,
+            Value::Number(number) => number.as_f64().is_some_and(|n| n > 0.0),
+            Value::String(value) => is_done(value),
+            _ => false,
+        };
+        if done && !habit.is_empty() {
+            history.entry(habit.to_string()).or_default().insert(date);
+        }
+    }
+
+    Ok(history)
+}
+
+/// Reads the completions of a CSV, JSON or iCalendar file, picking the reader
+/// from the extension and, for JSON, whether it's a Habitica user data export.
+pub async fn read_import_file(
+    path: &Path,
+    app_config: &AppConfig,
+    today: NaiveDate,
+) -> AppResult<ImportedHistory> {
+    let text = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|err| AppError::Import(format!("{}: {}", path.display(), err)))?;
+
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    match extension.as_str() {
+        "csv" => read_csv_history(&text),
+        "ics" => read_ics_history(&text, app_config, today),
+        "json" => {
+            let json: Value = serde_json::from_str(&text)
+                .map_err(|err| AppError::Import(format!("{}: {}", path.display(), err)))?;
+            if is_habitica_export(&json) {
+                read_habitica_history(&json, app_config.utc_offset, today)
+            } else {
+                read_json_history(&json)
+            }
+        }
+        _ => Err(AppError::Import(format!(
+            "{} isn't a .csv, .json or .ics file",
+            path.display()
+        ))),
+    }
+}
+
+/// Row where a grid for `month` goes in a year tab, keeping grids newest first:
+/// above the first older month, or below the last grid.
+fn grid_position(values: &mut Vec<Vec<Value>>, month: u32) -> usize {
+    let older_month_row = valid_months(values)
+        .into_iter()
+        .filter(|(name, _)| Month::from_str(name).is_ok_and(|m| (u8::from(m) as u32) < month))
+        .map(|(_, index)| index - 1)
+        .min();
+    if let Some(row) = older_month_row {
+        return row;
+    }
+
+    while values
+        .last()
+        .is_some_and(|row| row.iter().all(|cell| cell == ""))
+    {
+        values.pop();
+    }
+    if values.is_empty() {
+        return 0;
+    }
+
+    // leave a blank spacer row below the last grid
+    values.push(Vec::new());
+    values.len()
+}
+
+fn splice_rows(values: &mut Vec<Vec<Value>>, start_row: usize, rows: Vec<Vec<Value>>) {
+    if values.len() < start_row {
+        values.resize(start_row, Vec::new());
+    }
+    values.splice(start_row..start_row, rows);
+}
+
+/// A year tab as it will look once the import's structural requests are applied.
+struct YearTab {
+    sheet_name: String,
+    sheet_id: i32,
+    values: Vec<Vec<Value>>,
+}
+
+/// Writes `imported` completions into the month grids, after asking where each
+/// habit goes. Missing Config rows, year tabs, month grids and habit rows are
+/// created in one `batch_update`, then the cells are marked in batches.
+pub async fn import_history(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    imported: ImportedHistory,
+    source: &str,
+    today: NaiveDate,
+) -> AppResult<()> {
+    let imported: ImportedHistory = imported
+        .into_iter()
+        .map(|(habit, days)| {
+            (
+                habit,
+                days.into_iter().filter(|day| *day <= today).collect(),
+            )
+        })
+        .filter(|(_, days): &(String, BTreeSet<NaiveDate>)| !days.is_empty())
+        .collect();
+    if imported.is_empty() {
+        println!("Nothing to import, no completions found up to today.");
+        return Ok(());
+    }
+
+    let year_sheets = get_year_sheets(hub, app_config).await?;
+    let mut sheet_names = vec![app_config.config_sheet_name.clone()];
+    sheet_names.extend(year_sheets.iter().map(|(_, sheet_name)| sheet_name.clone()));
+
+    let mut retry_policy = RetryPolicy::default();
+    let mut request = hub
+        .spreadsheets()
+        .values_batch_get(&app_config.spreadsheet_id)
+        .delegate(&mut retry_policy);
+    for sheet_name in &sheet_names {
+        request = request.add_ranges(&quote_sheet_name(sheet_name));
+    }
+    let mut fetched = execute(request.doit())
+        .await?
+        .value_ranges
+        .unwrap_or_default()
+        .into_iter()
+        .map(|value_range| value_range.values.unwrap_or_default());
+
+    let config_values = fetched.next().unwrap_or_default();
+    let config_habits = get_habits(&config_values, config_table::START_ROW_INDEX);
+    let mut config_names: Vec<String> = config_habits.keys().cloned().collect();
+    config_names.sort();
+
+    // map every imported habit, matching Config names without asking
+    let mut targets: BTreeMap<String, BTreeSet<NaiveDate>> = BTreeMap::new();
+    let mut new_habits: BTreeSet<String> = BTreeSet::new();
+    let mut mapping_rows = vec![vec![
+        String::from("Imported"),
+        String::from("Habit"),
+        String::from("Done"),
+        String::from("From"),
+        String::from("To"),
+    ]];
+    for (habit, days) in &imported {
+        let known = config_names
+            .iter()
+            .find(|name| name.eq_ignore_ascii_case(habit));
+        let mapping = match known {
+            Some(name) => HabitMapping::Existing(name.clone()),
+            None => get_user_input_habit_mapping(habit, days.len(), &config_names)?,
+        };
+
+        let (target, label) = match mapping {
+            HabitMapping::Create => {
+                new_habits.insert(habit.clone());
+                (habit.clone(), format!("{} (new)", habit))
+            }
+            HabitMapping::Existing(name) => (name.clone(), name),
+            HabitMapping::Skip => {
+                mapping_rows.push(vec![habit.clone(), String::from("skipped")]);
+                continue;
+            }
+        };
+
+        mapping_rows.push(vec![
+            habit.clone(),
+            label,
+            days.len().to_string(),
+            days.first().unwrap().to_string(),
+            days.last().unwrap().to_string(),
+        ]);
+        targets.entry(target).or_default().extend(days);
+    }
+
+    let sheet_ids = get_sheet_ids(hub, app_config).await?;
+    let config_sheet_id = sheet_ids
+        .get(&app_config.config_sheet_name)
+        .copied()
+        .ok_or_else(|| {
+            AppError::SheetStructure(format!(
+                "'{}' sheet is missing",
+                app_config.config_sheet_name
+            ))
+        })?;
+    let mut next_id = next_sheet_id(&sheet_ids);
+    let mut requests = Vec::new();
+    let mut new_sheets: Vec<(String, i32)> = Vec::new();
+
+    let new_habits: Vec<String> = new_habits.into_iter().collect();
+    if !new_habits.is_empty() {
+        let start_row = config_habits
+            .values()
+            .max()
+            .map_or(config_table::START_ROW_INDEX, |row| row + 1);
+        let (config_requests, _) =
+            config_habit_requests(config_sheet_id, start_row as i32, &new_habits);
+        requests.extend(config_requests);
+    }
+
+    let mut active_habits: Vec<String> = get_active_habits(&config_values, 0).into_keys().collect();
+    active_habits.extend(new_habits.iter().cloned());
+
+    let mut year_tabs: BTreeMap<i32, YearTab> = year_sheets
+        .into_iter()
+        .zip(fetched)
+        .map(|((year, sheet_name), values)| {
+            let sheet_id = sheet_ids[&sheet_name];
+            (
+                year,
+                YearTab {
+                    sheet_name,
+                    sheet_id,
+                    values,
+                },
+            )
+        })
+        .collect();
+
+    // habits done per month, keyed by the month's first day
+    let mut month_habits: BTreeMap<NaiveDate, BTreeSet<&String>> = BTreeMap::new();
+    for (habit, days) in &targets {
+        for day in days {
+            month_habits
+                .entry(day.with_day(1).unwrap())
+                .or_default()
+                .insert(habit);
+        }
+    }
+
+    let (mut n_grid, mut n_habit_row) = (0, 0);
+    for (month_start, habits) in &month_habits {
+        let year = month_start.year();
+        let year_tab = year_tabs.entry(year).or_insert_with(|| {
+            let sheet_name = user_sheet_name(&year.to_string(), app_config.user.as_deref());
+            requests.push(add_sheet_request(&sheet_name, Some(next_id), None, 500, 32));
+            new_sheets.push((sheet_name.clone(), next_id));
+            next_id += 1;
+            YearTab {
+                sheet_name,
+                sheet_id: next_id - 1,
+                values: Vec::new(),
+            }
+        });
+
+        let month_name = month_start.format("%B").to_string();
+        match valid_months(&year_tab.values).get(&month_name) {
+            None => {
+                let mut grid_habits: BTreeSet<String> = active_habits.iter().cloned().collect();
+                grid_habits.extend(habits.iter().map(|habit| habit.to_string()));
+                let grid_habits: Vec<String> = grid_habits.into_iter().collect();
+
+                let start_row = grid_position(&mut year_tab.values, month_start.month());
+                let month_time = month_start.and_hms_opt(0, 0, 0).unwrap().and_utc();
+                let (grid_requests, grid_rows) = template_grid_requests(
+                    year_tab.sheet_id,
+                    start_row as i32,
+                    &month_time,
+                    &grid_habits,
+                );
+
+                requests.extend(grid_requests);
+                splice_rows(&mut year_tab.values, start_row, grid_rows);
+                n_grid += 1;
+            }
+            Some(index) => {
+                let grid_habits = get_habits(&year_tab.values, *index);
+                let missing: Vec<String> = habits
+                    .iter()
+                    .filter(|habit| !grid_habits.contains_key(**habit))
+                    .map(|habit| habit.to_string())
+                    .collect();
+                if missing.is_empty() {
+                    continue;
+                }
+
+                let start_row = index + grid_habits.len();
+                let n_day = get_dates(&year_tab.values, *index).len();
+                let (row_requests, rows) =
+                    habit_rows_requests(year_tab.sheet_id, start_row as i32, n_day, &missing);
+
+                requests.extend(row_requests);
+                splice_rows(&mut year_tab.values, start_row, rows);
+                n_habit_row += missing.len();
+            }
+        }
+    }
+
+    // address every completion in the grids as they'll be laid out
+    let mut updated_cell: Vec<ValueRange> = Vec::new();
+    let mut changes: Vec<CellChange> = Vec::new();
+    let mut n_already_done = 0;
+    for (habit, days) in &targets {
+        for day in days {
+            let year_tab = year_tabs.get_mut(&day.year()).unwrap();
+            let index = valid_months(&year_tab.values)[&day.format("%B").to_string()];
+            let row = get_habits(&year_tab.values, index)[habit];
+            let Some(col) = get_dates(&year_tab.values, index)
+                .get(&(day.day() as usize))
+                .copied()
+            else {
+                continue;
+            };
+
+            if year_tab.values[row]
+                .get(col)
+                .is_some_and(|cell| cell == "TRUE")
+            {
+                n_already_done += 1;
+                continue;
+            }
+
+            let cell_address = get_cell_address(row + 1, col + 1);
+            changes.push(
+                CellChange::new(
+                    &year_tab.sheet_name,
+                    &cell_address,
+                    &year_tab.values,
+                    (row, col),
+                    "TRUE",
+                )
+                .with_subject(habit, Some(*day)),
+            );
+            set_data(
+                &mut updated_cell,
+                String::from("TRUE"),
+                cell_address,
+                &year_tab.sheet_name,
+            );
+            set_cell_value(&mut year_tab.values, row, col, "TRUE");
+        }
+    }
+
+    println!();
+    print_table(&mapping_rows);
+    println!();
+    println!(
+        "📥 {} completions to mark, {} already done. Creates {} habits, {} year tabs, \
+         {} month grids and {} habit rows in existing grids.",
+        changes.len(),
+        n_already_done,
+        new_habits.len(),
+        new_sheets.len(),
+        n_grid,
+        n_habit_row
+    );
+
+    if changes.is_empty() && requests.is_empty() {
+        println!("Everything is already in the sheet. 🎉");
+        return Ok(());
+    }
+
+    if app_config.dry_run {
+        let mut sheet_names: HashMap<i32, String> = year_tabs
+            .values()
+            .map(|year_tab| (year_tab.sheet_id, year_tab.sheet_name.clone()))
+            .collect();
+        sheet_names.insert(config_sheet_id, app_config.config_sheet_name.clone());
+        print_dry_run(&changes, &requests, &sheet_names);
+        return Ok(());
+    }
+
+    if !get_user_input_confirm_import()? {
+        return Err(AppError::UserCancelled);
+    }
+
+    if !requests.is_empty() {
+        send_batch_update(hub, app_config, requests).await?;
+        for (sheet_name, sheet_id) in &new_sheets {
+            remember_sheet_id(&app_config.spreadsheet_id, sheet_name, *sheet_id);
+        }
+
+        // one audit entry per new habit, the grids follow from the completions
+        let generated: Vec<CellChange> = new_habits
+            .iter()
+            .map(|habit| CellChange {
+                range: quote_sheet_name(&app_config.config_sheet_name),
+                habit: habit.clone(),
+                date: String::new(),
+                old: String::new(),
+                new: String::from("New habit from import"),
+            })
+            .collect();
+        append_audit_log(hub, app_config, "import", &generated).await;
+    }
+    // the cached grid predates the new rows, so skip its version check next time
+    clear_cached_sheet(app_config);
+
+    let mut n_updated = 0;
+    for chunk in updated_cell.chunks(IMPORT_BATCH_SIZE) {
+        let batch = BatchUpdateValuesRequest {
+            value_input_option: Some("USER_ENTERED".to_string()),
+            data: Some(chunk.to_vec()),
+            ..Default::default()
+        };
+
+        let response = execute(
+            hub.spreadsheets()
+                .values_batch_update(batch, &app_config.spreadsheet_id)
+                .delegate(&mut RetryPolicy::default())
+                .doit(),
+        )
+        .await?;
+        n_updated += response.total_updated_cells.unwrap_or(0);
+    }
+
+    println!("{} cells updated", n_updated);
+
+    let description = format!("Imported {} completions from {}", changes.len(), source);
+    append_audit_log(hub, app_config, "import", &changes).await;
+    record_session(app_config, description, changes).await;
+
+    println!("✅ Import complete! Your past quests are now on the record. 📜");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn done(history: &ImportedHistory, habit: &str) -> Vec<NaiveDate> {
+        history
+            .get(habit)
+            .map(|days| days.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    fn rows(rows: &[&[&str]]) -> Vec<Vec<Value>> {
+        rows.iter()
+            .map(|row| row.iter().map(|cell| json!(cell)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn parse_csv_handles_quoted_fields() {
+        let text = "\u{feff}date,habit,note\r\n\
+                    2024-01-01,\"Read, daily\",\"said \"\"hi\"\"\nthen left\"\r\n\
+                    ,,\n\
+                    2024-01-02,Run,";
+
+        assert_eq!(
+            parse_csv(text),
+            vec![
+                vec!["date", "habit", "note"],
+                vec!["2024-01-01", "Read, daily", "said \"hi\"\nthen left"],
+                vec!["2024-01-02", "Run", ""],
+            ]
+        );
+    }
+
+    #[test]
+    fn read_csv_history_reads_long_rows() {
+        let text = "Date,Habit,Value\n\
+                    2024-02-29T07:00:00,Read,TRUE\n\
+                    2024-03-01,Read,false\n\
+                    2024-03-01,\"Run, easy\",x\n\
+                    not a date,Read,TRUE\n";
+        let history = read_csv_history(text).unwrap();
+
+        assert_eq!(done(&history, "Read"), vec![date(2024, 2, 29)]);
+        assert_eq!(done(&history, "Run, easy"), vec![date(2024, 3, 1)]);
+    }
+
+    #[test]
+    fn read_csv_history_counts_every_row_without_value_column() {
+        let history = read_csv_history("date,habit\n2023-12-31,Read\n2024-01-01,Read\n").unwrap();
+
+        assert_eq!(
+            done(&history, "Read"),
+            vec![date(2023, 12, 31), date(2024, 1, 1)]
+        );
+    }
+
+    #[test]
+    fn read_csv_history_only_counts_loop_checks() {
+        let text = "Date,Run,Read\n2024-01-02,2,1\n2024-01-01,1,2\n2023-12-31,0,0\n";
+        let history = read_csv_history(text).unwrap();
+
+        assert_eq!(done(&history, "Run"), vec![date(2024, 1, 2)]);
+        assert_eq!(done(&history, "Read"), vec![date(2024, 1, 1)]);
+    }
+
+    #[test]
+    fn read_csv_history_counts_ones_without_loop_checks() {
+        let history = read_csv_history("Date,Run\n2024-01-01,1\n2024-01-02,0\n").unwrap();
+
+        assert_eq!(done(&history, "Run"), vec![date(2024, 1, 1)]);
+    }
+
+    #[test]
+    fn read_csv_history_needs_date_column() {
+        assert!(matches!(
+            read_csv_history("day,habit\n2024-01-01,Read\n"),
+            Err(AppError::Import(_))
+        ));
+        assert!(matches!(read_csv_history(""), Err(AppError::Import(_))));
+    }
+
+    #[test]
+    fn read_json_history_reads_values() {
+        let json = json!([
+            { "date": "2024-01-01", "habit": "Read", "value": true },
+            { "date": "2024-01-02", "habit": "Read", "value": false },
+            { "date": "2024-01-03", "habit": "Read", "value": 1 },
+            { "date": "2024-01-04", "habit": "Read", "value": "done" },
+            { "date": "2024-01-05", "habit": "Read" },
+            { "date": "2024-01-06", "habit": " ", "value": true },
+            { "habit": "Read", "value": true },
+        ]);
+        let history = read_json_history(&json).unwrap();
+
+        assert_eq!(
+            done(&history, "Read"),
+            vec![
+                date(2024, 1, 1),
+                date(2024, 1, 3),
+                date(2024, 1, 4),
+                date(2024, 1, 5),
+            ]
+        );
+        assert_eq!(history.len(), 1);
+    }
+
+    #[test]
+    fn read_json_history_needs_list() {
+        assert!(matches!(
+            read_json_history(&json!({ "date": "2024-01-01" })),
+            Err(AppError::Import(_))
+        ));
+    }
+
+    #[test]
+    fn grid_position_goes_above_older_month() {
+        let mut values = rows(&[
+            &["May", "1", "2"],
+            &["Read", "TRUE", "FALSE"],
+            &[],
+            &["March", "1", "2"],
+            &["Read", "FALSE", "TRUE"],
+        ]);
+
+        assert_eq!(grid_position(&mut values, 4), 3);
+        assert_eq!(grid_position(&mut values, 6), 0);
+    }
+
+    #[test]
+    fn grid_position_goes_below_last_grid() {
+        let mut values = rows(&[
+            &["March", "1", "2"],
+            &["Read", "TRUE", "FALSE"],
+            &["", ""],
+            &[],
+        ]);
+
+        assert_eq!(grid_position(&mut values, 2), 3);
+        assert_eq!(values.len(), 3);
+        assert!(values[2].is_empty());
+    }
+
+    #[test]
+    fn grid_position_starts_empty_tab_at_top() {
+        assert_eq!(grid_position(&mut Vec::new(), 1), 0);
+    }
+}
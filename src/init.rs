@@ -1,128 +1,558 @@
-use core::panic;
-use std::{collections::HashMap, path::PathBuf, str::FromStr};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    str::FromStr,
+};
 
-use chrono::{DateTime, Datelike, Utc};
-use google_sheets4::Sheets;
-use serde::Deserialize;
+use chrono::{DateTime, Datelike, Duration, FixedOffset, Utc};
+use cliclack::select;
+use google_sheets4::{Sheets, api::Request};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use time::Month;
 use yup_oauth2::{
-    ServiceAccountAuthenticator, hyper::client::HttpConnector, hyper_rustls::HttpsConnector,
-    read_service_account_key,
+    InstalledFlowAuthenticator, InstalledFlowReturnMethod, ServiceAccountAuthenticator,
+    authenticator::Authenticator,
+    hyper::{self, client::HttpConnector},
+    hyper_rustls::{self, HttpsConnector},
+    read_application_secret, read_service_account_key,
 };
 
-use crate::template_builder::{
-    auto_resize_dimension, generate_config_sheet, generate_sheet, generate_template_grid,
+use crate::{
+    audit_log::append_audit_log,
+    cache::{get_cache_version, read_cached_sheet, write_cached_sheet},
+    data_updater::{get_cell_address, quote_sheet_name},
+    dry_run::{CellChange, print_dry_run},
+    error::{AppError, AppResult},
+    executor::{RetryPolicy, execute},
+    helpers::add_sheet_request,
+    interaction::get_user_input_new_habits,
+    setup_wizard::run_setup_wizard,
+    sheet_parser::{
+        config_table, forget_sheet_ids, get_active_habits, get_sheet_ids, next_sheet_id,
+        remember_sheet_id,
+    },
+    template_builder::{config_sheet_requests, send_batch_update, template_grid_requests},
 };
 
-pub async fn load_app_config(date_time: DateTime<Utc>) -> AppConfig {
-    let config_path = dirs::config_dir()
-        .unwrap()
-        .join("habit_tracker/config.toml");
+pub fn app_config_dir() -> PathBuf {
+    dirs::config_dir().unwrap().join("habit_tracker")
+}
+
+pub fn config_file_path() -> PathBuf {
+    app_config_dir().join("config.toml")
+}
+
+pub fn credentials_path() -> PathBuf {
+    app_config_dir().join("credentials.json")
+}
+
+pub fn client_secret_path() -> PathBuf {
+    app_config_dir().join("client_secret.json")
+}
+
+pub fn token_cache_path(profile_name: &str) -> PathBuf {
+    app_config_dir().join(format!("tokens-{profile_name}.json"))
+}
+
+pub fn history_file_path(profile_name: &str) -> PathBuf {
+    app_config_dir().join(format!("history-{profile_name}.json"))
+}
+
+/// Secret the AuditLog chain is signed with, kept out of the spreadsheet so
+/// editors of the sheet can't re-sign rows they changed.
+pub fn audit_key_path(profile_name: &str) -> PathBuf {
+    app_config_dir().join(format!("audit-key-{profile_name}"))
+}
+
+pub async fn read_config_file() -> AppResult<Option<ConfigFile>> {
+    let config_path = config_file_path();
+    if !config_path.exists() {
+        return Ok(None);
+    }
 
-    let content = tokio::fs::read_to_string(config_path)
+    let content = tokio::fs::read_to_string(&config_path)
         .await
-        .expect("Failed to read config file");
+        .map_err(|err| AppError::Config(format!("failed to read config.toml: {err}")))?;
+    let mut config_file: ConfigFile = toml::from_str(&content)
+        .map_err(|err| AppError::Config(format!("failed to parse config.toml: {err}")))?;
+
+    // Configs written before profiles existed keep their settings at the top level.
+    if config_file.profiles.is_empty()
+        && let Some(spreadsheet_id) = config_file.spreadsheet_id.take()
+    {
+        config_file.profiles.insert(
+            DEFAULT_PROFILE.to_string(),
+            Profile {
+                spreadsheet_id,
+                auth_mode: config_file.auth_mode.take().unwrap_or_default(),
+                credentials_path: None,
+                timezone: default_timezone(),
+                user: None,
+                team_members: Vec::new(),
+                leaderboard_profiles: Vec::new(),
+                calendar_patterns: BTreeMap::new(),
+            },
+        );
+    }
+
+    Ok(Some(config_file))
+}
+
+pub async fn write_config_file(config_file: &ConfigFile) -> AppResult<()> {
+    let content = toml::to_string(config_file)
+        .map_err(|err| AppError::Config(format!("failed to serialize config: {err}")))?;
+    tokio::fs::write(config_file_path(), content)
+        .await
+        .map_err(|err| AppError::Config(format!("failed to write config.toml: {err}")))?;
+
+    Ok(())
+}
+
+pub async fn load_app_config(profile_name: Option<&str>) -> AppResult<AppConfig> {
+    let config_path = config_file_path();
+
+    if !config_path.exists() {
+        println!(
+            "No config found at {}. Let's set one up first.",
+            config_path.display()
+        );
+        println!();
+        run_setup_wizard(profile_name).await?;
+    }
+
+    let config_file = read_config_file()
+        .await?
+        .ok_or_else(|| AppError::Config(format!("{} not found", config_path.display())))?;
+
+    let profile_name = match profile_name {
+        Some(name) => name.to_string(),
+        None => select_profile(&config_file)?,
+    };
 
-    let mut app_config: AppConfig = toml::from_str(&content).expect("Failed to parse config.toml");
-    app_config.sheet_name = date_time.year().to_string();
+    let profile = config_file.profiles.get(&profile_name).ok_or_else(|| {
+        AppError::Config(format!(
+            "profile '{}' not found, create it with `habit_tracker init --profile {}`",
+            profile_name, profile_name
+        ))
+    })?;
 
-    app_config
+    let utc_offset = match FixedOffset::from_str(&profile.timezone) {
+        Ok(offset) => Duration::seconds(offset.local_minus_utc() as i64),
+        Err(_) => {
+            return Err(AppError::Config(format!(
+                "invalid timezone '{}' in profile '{}', expected an offset like +07:00",
+                profile.timezone, profile_name
+            )));
+        }
+    };
+
+    let credentials_path = match &profile.credentials_path {
+        Some(path) => path.clone(),
+        None => match profile.auth_mode {
+            AuthMode::ServiceAccount => credentials_path(),
+            AuthMode::Oauth => client_secret_path(),
+        },
+    };
+
+    let year = (Utc::now() + utc_offset).year();
+
+    Ok(AppConfig {
+        spreadsheet_id: profile.spreadsheet_id.clone(),
+        year,
+        sheet_name: user_sheet_name(&year.to_string(), profile.user.as_deref()),
+        config_sheet_name: user_sheet_name(CONFIG_SHEET_NAME, profile.user.as_deref()),
+        audit_sheet_name: user_sheet_name(AUDIT_LOG_SHEET_NAME, profile.user.as_deref()),
+        auth_mode: profile.auth_mode,
+        profile_name,
+        credentials_path,
+        utc_offset,
+        user: profile.user.clone(),
+        team_members: profile.team_members.clone(),
+        leaderboard_profiles: profile.leaderboard_profiles.clone(),
+        calendar_patterns: profile.calendar_patterns.clone(),
+        dry_run: false,
+    })
+}
+
+/// Sheets in a shared team spreadsheet are suffixed with their owner, e.g. `2026-alice`.
+pub fn user_sheet_name(base_name: &str, user: Option<&str>) -> String {
+    match user {
+        Some(user) => format!("{base_name}-{user}"),
+        None => base_name.to_string(),
+    }
 }
 
-pub async fn setup_authenticator() -> yup_oauth2::authenticator::Authenticator<
-    yup_oauth2::hyper_rustls::HttpsConnector<yup_oauth2::hyper::client::HttpConnector>,
+fn select_profile(config_file: &ConfigFile) -> AppResult<String> {
+    let mut profile_names: Vec<&String> = config_file.profiles.keys().collect();
+
+    match profile_names.len() {
+        0 => return Err(AppError::Config(String::from("no profiles configured"))),
+        1 => return Ok(profile_names[0].to_string()),
+        _ => {}
+    }
+
+    profile_names.sort();
+
+    let mut profile_selector = select("Select profile");
+    for name in profile_names {
+        profile_selector = profile_selector.item(name.clone(), name, "");
+    }
+
+    if let Some(default_profile) = &config_file.default_profile {
+        profile_selector = profile_selector.initial_value(default_profile.clone());
+    }
+
+    Ok(profile_selector.interact()?)
+}
+
+pub async fn setup_authenticator(
+    app_config: &AppConfig,
+) -> AppResult<
+    yup_oauth2::authenticator::Authenticator<
+        yup_oauth2::hyper_rustls::HttpsConnector<yup_oauth2::hyper::client::HttpConnector>,
+    >,
 > {
-    let creds_path: PathBuf = dirs::config_dir()
-        .unwrap()
-        .join("habit_tracker/credentials.json");
+    let creds_path: PathBuf = app_config.credentials_path.clone();
 
     println!("Credential path: {}", creds_path.display());
     println!();
 
-    let secret = read_service_account_key(&creds_path)
-        .await
-        .expect("Failed to read credentials.json");
+    if !creds_path.exists() {
+        return Err(AppError::Config(format!(
+            "credentials not found at {}",
+            creds_path.display()
+        )));
+    }
 
-    ServiceAccountAuthenticator::builder(secret)
-        .build()
-        .await
-        .expect("Failed to build authenticator")
+    let invalid_credentials = |err: std::io::Error| {
+        AppError::Config(format!("failed to read {}: {err}", creds_path.display()))
+    };
+
+    let auth = match app_config.auth_mode {
+        AuthMode::ServiceAccount => {
+            let secret = read_service_account_key(&creds_path)
+                .await
+                .map_err(invalid_credentials)?;
+
+            ServiceAccountAuthenticator::builder(secret).build().await
+        }
+        AuthMode::Oauth => {
+            let secret = read_application_secret(&creds_path)
+                .await
+                .map_err(invalid_credentials)?;
+
+            InstalledFlowAuthenticator::builder(secret, InstalledFlowReturnMethod::HTTPRedirect)
+                .persist_tokens_to_disk(token_cache_path(&app_config.profile_name))
+                .build()
+                .await
+        }
+    };
+
+    auth.map_err(|err| AppError::Auth(err.to_string()))
+}
+
+pub fn build_hub(
+    auth: Authenticator<HttpsConnector<HttpConnector>>,
+) -> AppResult<Sheets<HttpsConnector<HttpConnector>>> {
+    let https = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .map_err(|err| AppError::Network(format!("failed to load TLS root certificates: {err}")))?
+        .https_or_http()
+        .enable_http1()
+        .build();
+
+    Ok(Sheets::new(hyper::Client::builder().build(https), auth))
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMode {
+    /// Authenticate as a GCP service account; the sheet must be shared with its email.
+    #[default]
+    ServiceAccount,
+    /// Authenticate as yourself through the browser (installed-app flow).
+    Oauth,
+}
+
+pub const DEFAULT_PROFILE: &str = "default";
+pub const CONFIG_SHEET_NAME: &str = "Config";
+pub const TEAM_SHEET_NAME: &str = "Team";
+pub const LEADERBOARD_SHEET_NAME: &str = "Leaderboard";
+pub const AUDIT_LOG_SHEET_NAME: &str = "AuditLog";
+
+fn default_timezone() -> String {
+    String::from("+07:00")
+}
+
+#[derive(Deserialize, Serialize, Default)]
+pub struct ConfigFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spreadsheet_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    auth_mode: Option<AuthMode>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Profile {
+    pub spreadsheet_id: String,
+    #[serde(default)]
+    pub auth_mode: AuthMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credentials_path: Option<PathBuf>,
+    /// UTC offset used to decide what "today" is, e.g. "+07:00".
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// Namespaces this profile's year tabs and Config sheet in a shared team spreadsheet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub team_members: Vec<String>,
+    /// Other profiles (possibly in other spreadsheets) ranked alongside this one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub leaderboard_profiles: Vec<String>,
+    /// Calendar event titles that count as a habit on `import`, e.g.
+    /// `Workout = "gym*|*run*"`. Case-insensitive, `*` matches anything.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub calendar_patterns: BTreeMap<String, String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone)]
 pub struct AppConfig {
     pub spreadsheet_id: String,
+    pub year: i32,
     pub sheet_name: String,
+    pub config_sheet_name: String,
+    pub audit_sheet_name: String,
+    pub auth_mode: AuthMode,
+    pub profile_name: String,
+    pub credentials_path: PathBuf,
+    pub utc_offset: Duration,
+    pub user: Option<String>,
+    pub team_members: Vec<String>,
+    pub leaderboard_profiles: Vec<String>,
+    pub calendar_patterns: BTreeMap<String, String>,
+    /// Print the cells and structural changes that would be written instead of sending them.
+    pub dry_run: bool,
 }
 
+/// Makes sure the Config sheet, this year's sheet and the current month's grid
+/// exist, creating whatever is missing in a single `batch_update`, and returns
+/// the year sheet values without reading them back.
 pub async fn ensure_sheet_ready(
     hub: &Sheets<HttpsConnector<HttpConnector>>,
     app_config: &AppConfig,
     wib: &DateTime<Utc>,
-) -> Vec<Vec<Value>> {
-    let mut sheet = hub
-        .spreadsheets()
-        .values_get(&app_config.spreadsheet_id, &app_config.sheet_name)
-        .doit()
-        .await;
+) -> AppResult<Vec<Vec<Value>>> {
+    let sheet_ids = get_sheet_ids(hub, app_config).await?;
+    let config_sheet_id = sheet_ids.get(&app_config.config_sheet_name).copied();
+    let year_sheet_id = sheet_ids.get(&app_config.sheet_name).copied();
 
+    let current_month = wib.format("%B").to_string();
+    // read before the values, so an edit landing in between invalidates them next time
+    let version = match (config_sheet_id, year_sheet_id) {
+        (Some(_), Some(_)) => get_cache_version(hub, app_config).await,
+        _ => None,
+    };
+    if let Some(values) = version
+        .as_deref()
+        .and_then(|version| get_cached_values(app_config, version, &current_month))
     {
-        let _ = match hub
+        return Ok(values);
+    }
+
+    let mut ranges: Vec<String> = Vec::new();
+    if year_sheet_id.is_some() {
+        ranges.push(quote_sheet_name(&app_config.sheet_name));
+    }
+    if config_sheet_id.is_some() {
+        ranges.push(format!(
+            "{}!{}",
+            quote_sheet_name(&app_config.config_sheet_name),
+            config_table::RANGE
+        ));
+    }
+
+    let mut fetched: Vec<Vec<Vec<Value>>> = Vec::new();
+    if !ranges.is_empty() {
+        let mut retry_policy = RetryPolicy::default();
+        let mut request = hub
             .spreadsheets()
-            .values_get(&app_config.spreadsheet_id, "Config!A1:A3")
-            .doit()
-            .await
-        {
-            Ok(sheet) => sheet,
-            Err(_) => {
-                println!(
-                    "⚡ Sheet 'Config' missing from database. Initiating reconstruction protocol... 🚧"
-                );
-                match generate_config_sheet(hub, app_config).await {
-                    Ok(config_sheet) => config_sheet,
-                    Err(_) => panic!(
-                        "Creating config sheet failed, make sure you have internet connection"
-                    ),
-                }
-            }
-        };
-    }
-
-    let values = match sheet {
-        Ok((_, value_range)) => value_range,
-        Err(_) => {
+            .values_batch_get(&app_config.spreadsheet_id)
+            .delegate(&mut retry_policy);
+        for range in &ranges {
+            request = request.add_ranges(range);
+        }
+
+        let response = execute(request.doit()).await.inspect_err(|_| {
+            // a tab may have been renamed or deleted since the ids were cached
+            forget_sheet_ids(&app_config.spreadsheet_id);
+        })?;
+
+        fetched = response
+            .value_ranges
+            .unwrap_or_default()
+            .into_iter()
+            .map(|value_range| value_range.values.unwrap_or_default())
+            .collect();
+    }
+
+    let mut fetched = fetched.into_iter();
+    let mut values = match year_sheet_id {
+        Some(_) => fetched.next().unwrap_or_default(),
+        None => Vec::new(),
+    };
+    let mut config_values = match config_sheet_id {
+        Some(_) => fetched.next().unwrap_or_default(),
+        None => Vec::new(),
+    };
+
+    let mut requests: Vec<Request> = Vec::new();
+    let mut next_id = next_sheet_id(&sheet_ids);
+    let mut new_sheets: Vec<(String, i32)> = Vec::new();
+    // one audit entry per generated sheet or grid rather than one per cell
+    let mut generated: Vec<CellChange> = Vec::new();
+
+    if config_sheet_id.is_none() {
+        println!(
+            "⚡ Sheet '{}' missing from database. Initiating reconstruction protocol... 🚧",
+            app_config.config_sheet_name
+        );
+
+        let habits = get_user_input_new_habits()?;
+        let (config_requests, config_rows) =
+            config_sheet_requests(&app_config.config_sheet_name, next_id, &habits);
+
+        requests.extend(config_requests);
+        generated.push(generated_change(
+            &app_config.config_sheet_name,
+            &config_rows,
+            format!("Config sheet with {} habits", habits.len()),
+        ));
+        config_values = config_rows;
+        new_sheets.push((app_config.config_sheet_name.clone(), next_id));
+        next_id += 1;
+    }
+
+    let year_sheet_id = match year_sheet_id {
+        Some(sheet_id) => sheet_id,
+        None => {
             println!(
                 "⚡ Sheet '{}' missing from database. Initiating reconstruction protocol... 🚧",
                 app_config.sheet_name
             );
 
-            generate_sheet(&hub, &app_config, &app_config.sheet_name, Some(0)).await;
-            let (_, sheet_id) = generate_template_grid(&hub, &app_config, &wib).await;
-            auto_resize_dimension(&hub, &app_config, sheet_id).await;
-
-            sheet = hub
-                .spreadsheets()
-                .values_get(&app_config.spreadsheet_id, &app_config.sheet_name)
-                .doit()
-                .await;
-
-            match sheet {
-                Ok((_, value_range)) => {
-                    println!(
-                        "✅ Sheet '{}' created successfully! You’re all set to continue. 🎉",
-                        app_config.sheet_name
-                    );
-                    value_range
-                }
-                Err(_) => {
-                    panic!("Creating new sheet failed, make sure you have internet connection")
-                }
-            }
+            requests.push(add_sheet_request(
+                &app_config.sheet_name,
+                Some(next_id),
+                Some(0),
+                500,
+                32,
+            ));
+            new_sheets.push((app_config.sheet_name.clone(), next_id));
+            generated.push(generated_change(
+                &app_config.sheet_name,
+                &[],
+                String::from("Year sheet"),
+            ));
+            next_id
+        }
+    };
+
+    if !valid_months(&values).contains_key(&current_month) {
+        println!(
+            "⚡ '{}' missing from database. Initiating reconstruction protocol... 🚧",
+            current_month
+        );
+
+        let mut habits: Vec<String> = get_active_habits(&config_values, 0).into_keys().collect();
+        habits.sort();
+
+        let (grid_requests, mut grid_rows) = template_grid_requests(year_sheet_id, 0, wib, &habits);
+        requests.extend(grid_requests);
+        generated.push(generated_change(
+            &app_config.sheet_name,
+            &grid_rows,
+            format!("{} grid with {} habits", current_month, habits.len()),
+        ));
+
+        // the new month is inserted above the existing ones
+        grid_rows.append(&mut values);
+        values = grid_rows;
+    }
+
+    if !requests.is_empty() && app_config.dry_run {
+        let mut sheet_names: HashMap<i32, String> = new_sheets
+            .iter()
+            .map(|(sheet_name, sheet_id)| (*sheet_id, sheet_name.clone()))
+            .collect();
+        sheet_names.insert(year_sheet_id, app_config.sheet_name.clone());
+
+        print_dry_run(&[], &requests, &sheet_names);
+
+        // carry on with the grid as it would look, without caching it
+        return Ok(values);
+    }
+
+    if !requests.is_empty() {
+        send_batch_update(hub, app_config, requests).await?;
+
+        for (sheet_name, sheet_id) in &new_sheets {
+            remember_sheet_id(&app_config.spreadsheet_id, sheet_name, *sheet_id);
         }
+
+        append_audit_log(hub, app_config, "grid generation", &generated).await;
+
+        println!("✅ Tracker rebuilt successfully! You’re all set to continue. 🎉");
+    } else if let Some(version) = version {
+        // after a rebuild the version read above is already stale, so nothing is cached
+        write_cached_sheet(app_config, &values, version);
+    }
+
+    Ok(values)
+}
+
+/// An audit entry covering the cells of `rows` written from A1 of `sheet_name`.
+fn generated_change(sheet_name: &str, rows: &[Vec<Value>], description: String) -> CellChange {
+    let n_col = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let range = match (rows.len(), n_col) {
+        (0, _) | (_, 0) => quote_sheet_name(sheet_name),
+        (n_row, n_col) => format!(
+            "{}!A1:{}",
+            quote_sheet_name(sheet_name),
+            get_cell_address(n_row, n_col)
+        ),
     };
 
-    values.values.unwrap_or_default()
+    CellChange {
+        range,
+        habit: String::new(),
+        date: String::new(),
+        old: String::new(),
+        new: description,
+    }
+}
+
+/// Returns the cached year sheet values if nobody changed the spreadsheet
+/// since they were read.
+fn get_cached_values(
+    app_config: &AppConfig,
+    version: &str,
+    current_month: &str,
+) -> Option<Vec<Vec<Value>>> {
+    let cached_sheet = read_cached_sheet(app_config)?;
+
+    if cached_sheet.version != version
+        || !valid_months(&cached_sheet.values).contains_key(current_month)
+    {
+        return None;
+    }
+
+    Some(cached_sheet.values)
 }
 
 pub fn valid_months(values: &Vec<Vec<Value>>) -> HashMap<String, usize> {
@@ -1,37 +1,56 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
-use cliclack::{multiselect, select};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use cliclack::{input, multiselect, select};
 use serde_json::Value;
 
-use crate::sheet_parser::{get_dates, get_habits};
+use crate::{
+    audit_log::AuditFilter,
+    error::AppResult,
+    heatmap_export::{DARK_THEME, LIGHT_THEME, Theme},
+    import::HabitMapping,
+    sheet_parser::{get_dates, get_habits},
+    stats::{Period, anchor_day},
+};
 
-pub fn get_user_input_exit_session() -> bool {
+pub fn get_user_input_exit_session() -> AppResult<bool> {
     let mut is_exit_selector = select("Wrap up your session? 📘");
     is_exit_selector = is_exit_selector.item(true, "Yes ✅", "");
     is_exit_selector = is_exit_selector.item(false, "No 🚫", "");
-    let is_exit = is_exit_selector.interact().unwrap();
-    is_exit
+    let is_exit = is_exit_selector.interact()?;
+    Ok(is_exit)
 }
 
-pub fn get_user_input_update_value() -> bool {
+pub fn get_user_input_retry() -> AppResult<bool> {
+    let mut retry_selector = select("Something went wrong. Try again?");
+    retry_selector = retry_selector.item(true, "Retry 🔁", "");
+    retry_selector = retry_selector.item(false, "Exit 🌙", "");
+    let is_retry = retry_selector.interact()?;
+    Ok(is_retry)
+}
+
+pub fn get_user_input_update_value() -> AppResult<bool> {
     let mut update_value_selector = select("Mark this habit as complete or not:");
     update_value_selector = update_value_selector.item(true, "Done ✅", "");
     update_value_selector = update_value_selector.item(false, "Skipped 🚫", "");
 
-    let update_value = update_value_selector.interact().unwrap();
-    update_value
+    let update_value = update_value_selector.interact()?;
+    Ok(update_value)
 }
 
-pub fn get_user_inputs(
-    values: &Vec<Vec<Value>>,
-    months: &HashMap<String, usize>,
-) -> (
+/// Selected habits and dates, the chosen month, and that month's habit rows and date columns.
+pub type UserInputs = (
     HashMap<String, bool>,
     HashMap<usize, bool>,
     String,
     HashMap<String, usize>,
     HashMap<usize, usize>,
-) {
+);
+
+pub fn get_user_inputs(
+    values: &Vec<Vec<Value>>,
+    months: &HashMap<String, usize>,
+) -> AppResult<UserInputs> {
     let mut month_selector = select("Select month");
 
     let mut sorted_month_by_index: Vec<(&String, &usize)> = months.iter().collect();
@@ -41,7 +60,7 @@ pub fn get_user_inputs(
         month_selector = month_selector.item(month.clone(), month, "");
     }
 
-    let selected_month = month_selector.interact().unwrap();
+    let selected_month = month_selector.interact()?;
 
     println!(
         "Selected month {} with index {}",
@@ -54,19 +73,19 @@ pub fn get_user_inputs(
     let habits = get_habits(values, index.clone());
     let dates = get_dates(values, index.clone());
 
-    let selected_habits = get_user_input_habit(&habits);
-    let selected_dates = get_user_input_date(&dates);
+    let selected_habits = get_user_input_habit(&habits)?;
+    let selected_dates = get_user_input_date(&dates)?;
 
-    (
+    Ok((
         selected_habits,
         selected_dates,
         cur_month.to_string(),
         habits,
         dates,
-    )
+    ))
 }
 
-pub fn get_user_input_date(dates: &HashMap<usize, usize>) -> HashMap<usize, bool> {
+pub fn get_user_input_date(dates: &HashMap<usize, usize>) -> AppResult<HashMap<usize, bool>> {
     let mut sorted_date: Vec<(&usize, &usize)> = dates.iter().collect();
     sorted_date.sort_by_key(|(d, _)| *d);
 
@@ -76,14 +95,32 @@ pub fn get_user_input_date(dates: &HashMap<usize, usize>) -> HashMap<usize, bool
         date_selector = date_selector.item(date.clone(), date, "");
     }
 
-    let selected_dates = date_selector.interact().unwrap();
+    let selected_dates = date_selector.interact()?;
     let selected_dates: HashMap<usize, bool> =
         selected_dates.into_iter().map(|h| (h, false)).collect();
 
-    selected_dates
+    Ok(selected_dates)
 }
 
-pub fn get_user_input_habit(habits: &HashMap<String, usize>) -> HashMap<String, bool> {
+/// Starts with `done_dates` checked, so unchecking a day marks it as not done.
+pub fn get_user_input_done_dates(
+    habit: &str,
+    dates: &[usize],
+    done_dates: &[usize],
+) -> AppResult<Vec<usize>> {
+    let mut date_selector = multiselect(format!("{}: days done", habit))
+        .initial_values(done_dates.to_vec())
+        .required(false);
+
+    for date in dates {
+        date_selector = date_selector.item(*date, date, "");
+    }
+
+    let selected_dates = date_selector.interact()?;
+    Ok(selected_dates)
+}
+
+pub fn get_user_input_habit(habits: &HashMap<String, usize>) -> AppResult<HashMap<String, bool>> {
     let mut habit_selector = multiselect("Select habits");
     let mut sorted_habit: Vec<_> = habits.keys().cloned().collect();
     sorted_habit.sort();
@@ -92,9 +129,248 @@ pub fn get_user_input_habit(habits: &HashMap<String, usize>) -> HashMap<String,
         habit_selector = habit_selector.item(habit.clone(), &habit, "");
     }
 
-    let selected_habits = habit_selector.interact().unwrap();
+    let selected_habits = habit_selector.interact()?;
     let selected_habits: HashMap<String, bool> =
         selected_habits.into_iter().map(|h| (h, false)).collect();
 
-    selected_habits
+    Ok(selected_habits)
+}
+
+pub fn get_user_input_new_habits() -> AppResult<Vec<String>> {
+    let user_inputs: String = input("Enter you habits (comma separated):")
+        .placeholder("e.g. reading, exercise, journaling")
+        .validate(|s: &String| {
+            if s.trim().is_empty() {
+                Err("Please enter at least one habit")
+            } else {
+                Ok(())
+            }
+        })
+        .interact()?;
+
+    let mut habits: Vec<String> = user_inputs
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    habits.sort();
+    Ok(habits)
+}
+
+pub fn get_user_input_period(today: NaiveDate) -> AppResult<Period> {
+    let last_week = today - Duration::days(7);
+    let last_month = today.with_day(1).unwrap() - Duration::days(1);
+
+    let mut period_selector = select("Select period");
+    period_selector = period_selector.item(1, "This week", "");
+    period_selector = period_selector.item(2, "Last week", "");
+    period_selector = period_selector.item(3, "This month", "");
+    period_selector = period_selector.item(4, "Last month", "");
+
+    let period = match period_selector.interact()? {
+        1 => Period::week_of(today),
+        2 => Period::week_of(last_week),
+        3 => Period::month_of(today),
+        4 => Period::month_of(last_month),
+        _ => unreachable!("Invalid selection"),
+    };
+
+    Ok(period)
+}
+
+pub fn get_user_input_audit_filter(habits: &BTreeSet<String>) -> AppResult<AuditFilter> {
+    let mut filter_selector = select("Which changes would you like to see?");
+    filter_selector = filter_selector.item(1, "Everything 📜", "");
+    if !habits.is_empty() {
+        filter_selector = filter_selector.item(2, "One habit 🎯", "");
+    }
+    filter_selector = filter_selector.item(3, "One date 📅", "");
+
+    let filter = match filter_selector.interact()? {
+        1 => AuditFilter::All,
+        2 => {
+            let mut habit_selector = select("Select habit");
+            for habit in habits {
+                habit_selector = habit_selector.item(habit.clone(), habit, "");
+            }
+            AuditFilter::Habit(habit_selector.interact()?)
+        }
+        3 => {
+            let date: String = input("Enter a date (YYYY-MM-DD):")
+                .placeholder("e.g. 2026-03-01")
+                .validate(
+                    |s: &String| match NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d") {
+                        Ok(_) => Ok(()),
+                        Err(_) => Err("Please enter a date like 2026-03-01"),
+                    },
+                )
+                .interact()?;
+            // already validated above
+            AuditFilter::Date(NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").unwrap())
+        }
+        _ => unreachable!("Invalid selection"),
+    };
+
+    Ok(filter)
+}
+
+pub fn get_user_input_heatmap_habit(habits: &BTreeSet<String>) -> AppResult<Option<String>> {
+    let mut habit_selector = select("Which habit?");
+    habit_selector = habit_selector.item(None, "All habits combined 🌈", "");
+    for habit in habits {
+        habit_selector = habit_selector.item(Some(habit.clone()), habit, "");
+    }
+
+    let habit = habit_selector.interact()?;
+    Ok(habit)
+}
+
+pub fn get_user_input_heatmap_period(
+    months: &HashMap<String, usize>,
+    year: i32,
+    today: NaiveDate,
+) -> AppResult<Period> {
+    let anchor = anchor_day(year, today);
+    let (quarter_label, year_label) = if year == today.year() {
+        (String::from("This quarter"), String::from("This year"))
+    } else {
+        (Period::quarter_of(anchor).label, format!("All of {}", year))
+    };
+
+    let mut period_selector = select("Select period");
+    period_selector = period_selector.item(1, "A month", "");
+    period_selector = period_selector.item(2, quarter_label, "");
+    period_selector = period_selector.item(3, year_label, "");
+
+    let period = match period_selector.interact()? {
+        1 => get_user_input_month_period(months, year, today)?,
+        2 => Period::quarter_of(anchor),
+        3 => Period::year_of(anchor),
+        _ => unreachable!("Invalid selection"),
+    };
+
+    Ok(period)
+}
+
+pub fn get_user_input_theme() -> AppResult<&'static Theme> {
+    let mut theme_selector = select("Select theme");
+    theme_selector = theme_selector.item(1, "Light ☀️", "");
+    theme_selector = theme_selector.item(2, "Dark 🌙", "");
+
+    let theme = match theme_selector.interact()? {
+        1 => &LIGHT_THEME,
+        2 => &DARK_THEME,
+        _ => unreachable!("Invalid selection"),
+    };
+
+    Ok(theme)
+}
+
+/// Callers set their own extension, e.g. `.svg` and `.png` side by side.
+pub fn get_user_input_export_path(default_path: &str) -> AppResult<String> {
+    let path: String = input("Save as:")
+        .default_input(default_path)
+        .placeholder(default_path)
+        .interact()?;
+
+    Ok(path.trim().to_string())
+}
+
+/// One of the months on the year tab.
+fn get_user_input_month_period(
+    months: &HashMap<String, usize>,
+    year: i32,
+    today: NaiveDate,
+) -> AppResult<Period> {
+    let mut month_selector = select("Select month");
+
+    let mut sorted_month_by_index: Vec<(&String, &usize)> = months.iter().collect();
+    sorted_month_by_index.sort_by_key(|(_, i)| *i);
+
+    for (month, _) in sorted_month_by_index {
+        month_selector = month_selector.item(month.clone(), month, "");
+    }
+
+    let month = month_selector.interact()?;
+    let first_day =
+        NaiveDate::parse_from_str(&format!("{} {} 1", year, month), "%Y %B %d").unwrap_or(today);
+
+    Ok(Period::month_of(first_day))
+}
+
+pub fn get_user_input_report_period(
+    months: &HashMap<String, usize>,
+    year: i32,
+    today: NaiveDate,
+) -> AppResult<Period> {
+    let mut period_selector = select("Report on");
+    period_selector = period_selector.item(1, "A month", "");
+    period_selector = period_selector.item(2, "This week", "");
+    period_selector = period_selector.item(3, "Last week", "");
+    period_selector = period_selector.item(4, "Another ISO week", "");
+
+    let period = match period_selector.interact()? {
+        1 => get_user_input_month_period(months, year, today)?,
+        2 => Period::week_of(today),
+        3 => Period::week_of(today - Duration::days(7)),
+        4 => {
+            let week: String = input("Enter an ISO week:")
+                .placeholder(&today.format("e.g. %G-W%V").to_string())
+                .validate(|s: &String| match parse_iso_week(s) {
+                    Some(_) => Ok(()),
+                    None => Err("Please enter a week like 2026-W09"),
+                })
+                .interact()?;
+            // already validated above
+            Period::week_of(parse_iso_week(&week).unwrap())
+        }
+        _ => unreachable!("Invalid selection"),
+    };
+
+    Ok(period)
+}
+
+pub fn get_user_input_habit_mapping(
+    imported: &str,
+    n_done: usize,
+    config_habits: &[String],
+) -> AppResult<HabitMapping> {
+    let prompt = format!("Where should '{}' ({} completions) go?", imported, n_done);
+    let mut mapping_selector = select(prompt);
+    mapping_selector = mapping_selector.item(0, format!("New habit '{}' ✨", imported), "");
+    for (i, habit) in config_habits.iter().enumerate() {
+        mapping_selector = mapping_selector.item(i + 2, habit, "");
+    }
+    mapping_selector = mapping_selector.item(1, "Skip it 🚫", "");
+
+    let mapping = match mapping_selector.interact()? {
+        0 => HabitMapping::Create,
+        1 => HabitMapping::Skip,
+        i => HabitMapping::Existing(config_habits[i - 2].clone()),
+    };
+
+    Ok(mapping)
+}
+
+pub fn get_user_input_confirm_import() -> AppResult<bool> {
+    let mut is_import_selector = select("Write these completions to the sheet?");
+    is_import_selector = is_import_selector.item(true, "Yes, import them 📥", "");
+    is_import_selector = is_import_selector.item(false, "No, cancel 🚫", "");
+    let is_import = is_import_selector.interact()?;
+    Ok(is_import)
+}
+
+pub fn get_user_input_habitica_checklists() -> AppResult<bool> {
+    let mut is_checklist_selector = select("Import daily checklist items as separate habits?");
+    is_checklist_selector = is_checklist_selector.item(false, "No, just the dailies 📋", "");
+    is_checklist_selector = is_checklist_selector.item(true, "Yes, one habit per item ☑️", "");
+    let is_checklist = is_checklist_selector.interact()?;
+    Ok(is_checklist)
+}
+
+/// The Monday of a week written as `2026-W09`.
+fn parse_iso_week(week: &str) -> Option<NaiveDate> {
+    let (year, week) = week.trim().split_once("-W")?;
+    NaiveDate::from_isoywd_opt(year.parse().ok()?, week.parse().ok()?, Weekday::Mon)
 }
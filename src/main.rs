@@ -1,285 +1,294 @@
+mod audit_log;
+mod cache;
+mod cli;
 mod data_updater;
+mod drive;
+mod dry_run;
+mod error;
+mod executor;
+mod export;
+mod grid_editor;
+mod habitica;
+mod heatmap;
+mod heatmap_export;
 mod helpers;
+mod history;
+mod html_report;
+mod ical;
+mod import;
 mod init;
 mod interaction;
+mod markdown_report;
+mod pixel_font;
+mod setup_wizard;
 mod sheet_parser;
+mod stats;
+mod team_report;
 mod template_builder;
+mod workbook_export;
 
 use crate::{
-    data_updater::{bulk_update, get_cell_address, set_data, update_today_progress},
-    helpers::{delete_rows, group_consecutive},
-    init::{AppConfig, ensure_sheet_ready, load_app_config, setup_authenticator, valid_months},
-    interaction::{get_user_input_exit_session, get_user_input_habit, get_user_inputs},
-    sheet_parser::{
-        config_table, get_dates, get_habits, get_sheet_id, get_today_progresses, print_activities,
-        print_current_month_total_progress,
+    audit_log::print_audit_log,
+    cli::{CliArgs, Command, ExportFormat, parse_args},
+    data_updater::{
+        MonthGrid, plan_bulk_update, submit_update, update_habit_config, update_today_progress,
+        write_activities,
     },
+    error::{AppError, AppResult},
+    export::{ExportFilter, export_history},
+    grid_editor::run_grid_editor,
+    heatmap::print_heatmap,
+    heatmap_export::export_heatmap_images,
+    history::undo_last_session,
+    html_report::generate_html_report,
+    import::{import_history, read_import_file},
+    init::{
+        AppConfig, build_hub, ensure_sheet_ready, load_app_config, setup_authenticator,
+        valid_months,
+    },
+    interaction::{get_user_input_exit_session, get_user_input_retry, get_user_inputs},
+    markdown_report::export_markdown_report,
+    setup_wizard::run_setup_wizard,
+    sheet_parser::{get_today_progresses, print_activities, print_current_month_total_progress},
+    team_report::{print_leaderboard, print_team_summary},
+    workbook_export::export_workbook,
 };
-use chrono::{Duration, Utc};
+use chrono::Utc;
 use cliclack::select;
-use core::panic;
-use google_sheets4::{
-    Sheets,
-    api::{BatchUpdateSpreadsheetRequest, BatchUpdateValuesRequest, Request, ValueRange},
-};
-use std::{collections::HashMap, usize};
-use yup_oauth2::{
-    hyper::{self},
-    hyper_rustls,
-};
+use std::collections::HashMap;
+
+#[derive(Clone, PartialEq, Eq)]
+enum Submit {
+    Yes,
+    Preview,
+    No,
+}
 
 #[tokio::main]
 async fn main() {
+    let cli_args = parse_args();
+
+    if let Err(err) = run(cli_args).await {
+        match err {
+            AppError::UserCancelled => print!("\nSee you tomorrow!\n"),
+            _ => eprintln!("\n❌ {err}"),
+        }
+        std::process::exit(err.exit_code());
+    }
+}
+
+async fn run(cli_args: CliArgs) -> AppResult<()> {
+    if let Command::Init = cli_args.command {
+        return run_setup_wizard(cli_args.profile.as_deref()).await;
+    }
+
+    let mut app_config: AppConfig = load_app_config(cli_args.profile.as_deref()).await?;
+    app_config.dry_run = cli_args.dry_run;
+
     let utc_now = Utc::now();
-    let wib = utc_now + Duration::hours(7);
+    let wib = utc_now + app_config.utc_offset;
     let date_format = "%Y-%m-%d %H:%M";
 
     println!("{}", wib.format(date_format));
     println!();
 
-    let app_config: AppConfig = load_app_config(wib).await;
-
     println!(
-        "Spreadsheet id: {}, sheet name: {}",
-        app_config.spreadsheet_id, app_config.sheet_name
+        "Profile: {}, spreadsheet id: {}, sheet name: {}",
+        app_config.profile_name, app_config.spreadsheet_id, app_config.sheet_name
     );
+    if app_config.dry_run {
+        println!("🧪 Dry run: changes are printed instead of written to the sheet.");
+    }
 
-    let auth = setup_authenticator().await;
-
-    let https = hyper_rustls::HttpsConnectorBuilder::new()
-        .with_native_roots()
-        .unwrap()
-        .https_or_http()
-        .enable_http1()
-        .build();
+    let auth = setup_authenticator(&app_config).await?;
+    let hub = build_hub(auth)?;
 
-    let hub = Sheets::new(hyper::Client::builder().build(https), auth);
+    match cli_args.command {
+        Command::Undo => return undo_last_session(&hub, &app_config).await,
+        Command::History => return print_audit_log(&hub, &app_config).await,
+        Command::Report => {
+            if let Some(html_dir) = &cli_args.html_dir {
+                return generate_html_report(&hub, &app_config, html_dir, wib.date_naive()).await;
+            }
+        }
+        Command::Export => match cli_args.export_format {
+            Some(format @ (ExportFormat::Xlsx | ExportFormat::Ods)) => {
+                return export_workbook(&hub, &app_config, format, cli_args.output).await;
+            }
+            Some(format) => {
+                let filter = ExportFilter {
+                    habits: cli_args.habits,
+                    from: cli_args.from,
+                    to: cli_args.to,
+                };
+                return export_history(
+                    &hub,
+                    &app_config,
+                    format,
+                    &filter,
+                    cli_args.daily_summary,
+                    cli_args.output,
+                    wib.date_naive(),
+                )
+                .await;
+            }
+            None => {}
+        },
+        Command::Import => {
+            if let Some(path) = &cli_args.import_path {
+                let imported = read_import_file(path, &app_config, wib.date_naive()).await?;
+                ensure_sheet_ready(&hub, &app_config, &wib).await?;
+                let source = path.display().to_string();
+                return import_history(&hub, &app_config, imported, &source, wib.date_naive())
+                    .await;
+            }
+        }
+        _ => {}
+    }
 
     'main_loop: loop {
-        let mut values = ensure_sheet_ready(&hub, &app_config, &wib).await;
+        let session: AppResult<_> = async {
+            let values = ensure_sheet_ready(&hub, &app_config, &wib).await?;
+            let months: HashMap<String, usize> = valid_months(&values);
+            println!();
 
-        let mut months: HashMap<String, usize> = valid_months(&values);
-        println!();
-
-        get_today_progresses(&mut values, &mut months, &wib, &hub, &app_config).await;
+            get_today_progresses(&values, &months, &wib)?;
+            Ok((values, months))
+        }
+        .await;
+
+        let (mut values, months) = match session {
+            Ok(session) => session,
+            Err(err) if err.is_retryable() => {
+                eprintln!("❌ {err}");
+                if get_user_input_retry()? {
+                    continue 'main_loop;
+                }
+                break 'main_loop;
+            }
+            Err(err) => return Err(err),
+        };
 
         println!();
 
         let mut action_selector = select("How would you like to start?");
         action_selector = action_selector.item(1, "✅ Record today's accomplishments", "");
         action_selector = action_selector.item(2, "🔍 Browse & improve previous entries", "");
+        action_selector = action_selector.item(10, "🧮 Edit the month grid (full screen)", "");
         action_selector =
             action_selector.item(3, "dev sandbox, show total progress this month", "");
+        action_selector = action_selector.item(11, "🟩 Habit heatmap", "");
+        action_selector = action_selector.item(12, "🖼️ Export heatmap images", "");
+        action_selector = action_selector.item(13, "📝 Markdown report", "");
         action_selector = action_selector.item(4, "🌙 Rest for today (exit)", "");
         action_selector = action_selector.item(5, "dev sandbox, update habit config", "");
+        if !app_config.team_members.is_empty() {
+            action_selector = action_selector.item(6, "👥 Team summary", "");
+        }
+        if !app_config.team_members.is_empty() || !app_config.leaderboard_profiles.is_empty() {
+            action_selector = action_selector.item(7, "🏆 Leaderboard", "");
+        }
+        action_selector = action_selector.item(8, "↩️ Undo last change", "");
+        action_selector = action_selector.item(9, "📜 Change history", "");
 
-        let selected_action = action_selector.interact().unwrap();
-
-        match selected_action {
-            1 => {
-                update_today_progress(&hub, &app_config, &wib, &mut values, &months).await;
-            }
-            2 => {
-                let (mut selected_habits, mut selected_dates, cur_month, habits, dates) =
-                    get_user_inputs(&values, &months);
-
-                print_activities(
-                    &selected_dates,
-                    &selected_habits,
-                    &habits,
-                    &dates,
-                    &values,
-                    &cur_month,
-                    &app_config.sheet_name,
-                );
+        let selected_action = match action_selector.interact() {
+            Ok(action) => action,
+            Err(_) => break 'main_loop,
+        };
 
-                let mut is_update_selector = select("Submit selected activities?");
-                is_update_selector = is_update_selector.item(true, "yes", "");
-                is_update_selector = is_update_selector.item(false, "no", "");
+        if selected_action == 4 {
+            break 'main_loop;
+        }
 
-                let is_update = is_update_selector.interact().unwrap();
+        let result: AppResult<()> = async {
+            match selected_action {
+                1 => update_today_progress(&hub, &app_config, &wib, &mut values, &months).await,
+                2 => {
+                    let (mut selected_habits, mut selected_dates, cur_month, habits, dates) =
+                        get_user_inputs(&values, &months)?;
 
-                if is_update {
-                    bulk_update(
-                        &hub,
-                        &app_config,
-                        &mut values,
-                        &mut selected_dates,
-                        &mut selected_habits,
+                    print_activities(
+                        &selected_dates,
+                        &selected_habits,
                         &habits,
                         &dates,
+                        &values,
                         &cur_month,
-                    )
-                    .await;
-                }
-            }
-            3 => {
-                print_current_month_total_progress(&values);
-            }
-            4 => {
-                break 'main_loop;
-            }
-            5 => {
-                let config_sheet_name = "Config";
-                let range = format!("{config_sheet_name}!A1:C50");
-                let config_sheet = match hub
-                    .spreadsheets()
-                    .values_get(&app_config.spreadsheet_id, &range)
-                    .doit()
-                    .await
-                {
-                    Ok(sheet) => sheet,
-                    Err(_) => {
-                        panic!("Config sheet not found! Make sure you have internet connection.");
-                    }
-                };
-
-                let current_month_habits = get_habits(&values, 1);
-                let current_month_dates = get_dates(&values, 1);
-
-                let config_values = config_sheet.1.values.expect("Sheet has no values!");
-
-                let config_habits = get_habits(&config_values, config_table::START_ROW_INDEX);
-                let mut selected_habits = get_user_input_habit(&config_habits);
-                selected_habits.iter_mut().for_each(|(_, v)| *v = true);
-
-                let mut update_value_selector = select("Set habit");
-                update_value_selector = update_value_selector.item(true, "Active ✅", "");
-                update_value_selector = update_value_selector.item(false, "Inactive 🚫", "");
-
-                let update_value = update_value_selector.interact().unwrap();
-
-                let mut row_to_delete: Vec<String> = Vec::new();
-                if !update_value {
-                    for (habit_name, is_update) in selected_habits.iter_mut() {
-                        let cur_month_index = match current_month_habits.get(habit_name) {
-                            Some(v) => v,
-                            None => continue,
-                        };
-
-                        let mut deletable = true;
+                        &app_config.sheet_name,
+                    );
 
-                        for (_, date_index) in &current_month_dates {
-                            if values[*cur_month_index][*date_index] == "TRUE" {
-                                deletable = false;
-                                *is_update = false;
-                                println!(
-                                    "{} has activity history. Deactivation is not allowed.",
-                                    habit_name
-                                );
-                                break;
+                    let mut grid = MonthGrid {
+                        values: &mut values,
+                        cur_month: &cur_month,
+                        habits: &habits,
+                        dates: &dates,
+                    };
+                    let update =
+                        plan_bulk_update(&grid, &mut selected_dates, &mut selected_habits)?;
+
+                    while !update.targets.is_empty() {
+                        let mut is_update_selector = select("Submit selected activities?");
+                        is_update_selector = is_update_selector.item(Submit::Yes, "yes", "");
+                        is_update_selector =
+                            is_update_selector.item(Submit::Preview, "preview changes 🧪", "");
+                        is_update_selector = is_update_selector.item(Submit::No, "no", "");
+
+                        match is_update_selector.interact()? {
+                            Submit::Yes => break,
+                            Submit::No => return Ok(()),
+                            Submit::Preview => {
+                                let preview_config = AppConfig {
+                                    dry_run: true,
+                                    ..app_config.clone()
+                                };
+
+                                write_activities(&hub, &preview_config, &mut grid, &update).await?;
                             }
                         }
-
-                        if deletable {
-                            row_to_delete.push(habit_name.to_string());
-                        }
                     }
-                }
 
-                let update_value = if update_value { "TRUE" } else { "FALSE" };
-
-                let mut updated_cell: Vec<ValueRange> = Vec::new();
-                for (habit, is_update) in &selected_habits {
-                    if !is_update {
-                        continue;
-                    }
-
-                    let habit = config_habits.get(habit).unwrap();
-                    let cell_address =
-                        get_cell_address(*habit + 1, config_table::Column::IsActive.as_usize());
-
-                    set_data(
-                        &mut updated_cell,
-                        update_value.to_string(),
-                        cell_address,
-                        &config_sheet_name.to_string(),
-                    );
+                    submit_update(
+                        &hub,
+                        &app_config,
+                        &mut grid,
+                        &update,
+                        &selected_dates,
+                        &selected_habits,
+                    )
+                    .await
                 }
-
-                let batch = BatchUpdateValuesRequest {
-                    value_input_option: Some("USER_ENTERED".to_string()),
-                    data: Some(updated_cell),
-                    ..Default::default()
-                };
-
-                let result = hub
-                    .spreadsheets()
-                    .values_batch_update(batch, &app_config.spreadsheet_id)
-                    .doit()
-                    .await;
-
-                match result {
-                    Ok((_, response)) => {
-                        println!(
-                            "{} cells updated",
-                            response.total_updated_cells.unwrap_or(0)
-                        );
-                    }
-                    Err(err) => {
-                        eprint!("Update failed: {:?}", err);
-                    }
+                3 => {
+                    print_current_month_total_progress(&values);
+                    Ok(())
                 }
-
-                if !row_to_delete.is_empty() {
-                    let delete_msg = format!(
-                        "\nSuccessfully deleted: \n  {}\n",
-                        row_to_delete.join("\n  ")
-                    );
-
-                    let mut habit_to_delete_index: Vec<usize> = row_to_delete
-                        .iter()
-                        .filter_map(|name| current_month_habits.get(name).copied())
-                        .collect();
-
-                    habit_to_delete_index.sort();
-
-                    let sheet_id = get_sheet_id(&hub, &app_config, &app_config.sheet_name).await;
-
-                    let habit_to_delete_index_group = group_consecutive(&habit_to_delete_index);
-                    let mut delete_row_requests: Vec<Request> = Vec::new();
-
-                    let mut previous_group_deleted_row: i32 = 0;
-                    for group in habit_to_delete_index_group {
-                        let min_index = (group.iter().min().unwrap().clone() as i32)
-                            - previous_group_deleted_row;
-                        let max_index = (group.iter().max().unwrap().clone() as i32) + 1
-                            - previous_group_deleted_row;
-
-                        let delete_request =
-                            delete_rows(sheet_id, "ROWS".to_string(), min_index, max_index);
-                        delete_row_requests.push(delete_request);
-                        previous_group_deleted_row =
-                            previous_group_deleted_row + (max_index - min_index);
-                    }
-
-                    let update_batch = BatchUpdateSpreadsheetRequest {
-                        requests: Some(delete_row_requests),
-                        include_spreadsheet_in_response: None,
-                        response_include_grid_data: None,
-                        response_ranges: None,
-                    };
-
-                    let result = hub
-                        .spreadsheets()
-                        .batch_update(update_batch, &app_config.spreadsheet_id)
-                        .doit()
-                        .await;
-
-                    match result {
-                        Ok(_) => {
-                            println!("{delete_msg}");
-                        }
-                        Err(err) => {
-                            eprint!("Update failed: {:?}", err);
-                        }
-                    }
+                5 => update_habit_config(&hub, &app_config, &values).await,
+                6 => print_team_summary(&hub, &app_config, &wib).await,
+                7 => print_leaderboard(&hub, &app_config, &wib).await,
+                8 => undo_last_session(&hub, &app_config).await,
+                9 => print_audit_log(&hub, &app_config).await,
+                10 => run_grid_editor(&hub, &app_config, &mut values, &months, &wib).await,
+                11 => print_heatmap(&values, &months, app_config.year, wib.date_naive()),
+                12 => export_heatmap_images(&values, &app_config, wib.date_naive()).await,
+                13 => export_markdown_report(&hub, &app_config, &values, wib.date_naive()).await,
+                _ => unreachable!("Invalid selection"),
+            }
+        }
+        .await;
+
+        match result {
+            Ok(()) => {}
+            Err(AppError::UserCancelled) => println!("Action cancelled."),
+            Err(err) if err.is_retryable() => {
+                eprintln!("❌ {err}");
+                if get_user_input_retry()? {
+                    continue 'main_loop;
                 }
+                break 'main_loop;
             }
-            _ => unreachable!("Invalid selection"),
+            Err(err) => return Err(err),
         }
 
-        let is_exit = get_user_input_exit_session();
+        let is_exit = get_user_input_exit_session().unwrap_or(true);
 
         if is_exit {
             break 'main_loop;
@@ -287,4 +296,6 @@ async fn main() {
     }
 
     print!("\nSee you tomorrow!\n");
+
+    Ok(())
 }
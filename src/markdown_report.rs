@@ -0,0 +1,248 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+use chrono::{Datelike, Duration, NaiveDate};
+use cliclack::select;
+use google_sheets4::Sheets;
+use serde_json::Value;
+use yup_oauth2::{hyper::client::HttpConnector, hyper_rustls::HttpsConnector};
+
+use crate::{
+    AppConfig,
+    error::AppResult,
+    heatmap::day_progress,
+    html_report::get_year_histories,
+    init::valid_months,
+    interaction::{get_user_input_export_path, get_user_input_report_period},
+    sheet_parser::CompletionHistory,
+    stats::{Period, completion_rate, current_streak, done_days, longest_streak},
+};
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+/// Markdown for `period`: a habits × days table with totals, the best and
+/// worst habits and streaks, ready to paste into a journal or notes repo.
+pub fn markdown_report(history: &CompletionHistory, period: &Period, today: NaiveDate) -> String {
+    let days: Vec<NaiveDate> = (0..=(period.end - period.start).num_days())
+        .map(|day| period.start + Duration::days(day))
+        .collect();
+    let is_week = days.len() == 7;
+    let habits: Vec<&String> = history
+        .iter()
+        .filter(|(_, habit_days)| days.iter().any(|day| habit_days.contains_key(day)))
+        .map(|(habit, _)| habit)
+        .collect();
+
+    let mut markdown = format!("# Habit report: {}\n\n", period.label);
+    if habits.is_empty() {
+        markdown.push_str("Nothing was tracked in this period.\n");
+        return markdown;
+    }
+
+    let day_label = |day: &NaiveDate| {
+        if is_week {
+            day.format("%a %-d").to_string()
+        } else {
+            day.day().to_string()
+        }
+    };
+
+    markdown.push_str("| Habit |");
+    for day in &days {
+        markdown.push_str(&format!(" {} |", day_label(day)));
+    }
+    markdown.push_str(" Total |\n|---|");
+    markdown.push_str(&":-:|".repeat(days.len()));
+    markdown.push_str("--:|\n");
+
+    let mut day_totals = vec![0; days.len()];
+    for habit in &habits {
+        markdown.push_str(&format!("| {} |", escape_markdown(habit)));
+
+        let mut total = 0;
+        for (i, day) in days.iter().enumerate() {
+            let cell = match day_progress(history, Some(habit.as_str()), *day, today) {
+                Some((1, _)) => {
+                    total += 1;
+                    day_totals[i] += 1;
+                    "✅"
+                }
+                Some(_) => "❌",
+                None => "",
+            };
+            markdown.push_str(&format!(" {} |", cell));
+        }
+        markdown.push_str(&format!(" {} |\n", total));
+    }
+
+    markdown.push_str("| **Total** |");
+    for total in &day_totals {
+        markdown.push_str(&format!(" {} |", total));
+    }
+    markdown.push_str(&format!(" **{}** |\n\n", day_totals.iter().sum::<usize>()));
+
+    markdown.push_str("## Summary\n\n");
+    markdown.push_str(&format!(
+        "- **Total EXP:** {}\n- **Completion:** {:.0}%\n",
+        day_totals.iter().sum::<usize>(),
+        completion_rate(history, period, today) * 100.0
+    ));
+
+    // habits with nothing tracked yet, e.g. later this week, aren't ranked
+    let mut rates: Vec<(&String, f64)> = habits
+        .iter()
+        .filter_map(|habit| {
+            let (done, total) = days
+                .iter()
+                .filter_map(|day| day_progress(history, Some(habit.as_str()), *day, today))
+                .fold((0, 0), |(done, total), (day_done, day_total)| {
+                    (done + day_done, total + day_total)
+                });
+            (total > 0).then(|| (*habit, done as f64 / total as f64))
+        })
+        .collect();
+    rates.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let names_with_rate = |rate: f64| {
+        rates
+            .iter()
+            .filter(|(_, habit_rate)| *habit_rate == rate)
+            .map(|(habit, _)| escape_markdown(habit))
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+    if let (Some((_, best)), Some((_, worst))) = (rates.first(), rates.last()) {
+        markdown.push_str(&format!(
+            "- **Best:** {} ({:.0}%)\n",
+            names_with_rate(*best),
+            best * 100.0
+        ));
+        if worst < best {
+            markdown.push_str(&format!(
+                "- **Needs work:** {} ({:.0}%)\n",
+                names_with_rate(*worst),
+                worst * 100.0
+            ));
+        }
+    }
+
+    let as_of = period.end.min(today);
+    markdown.push_str(&format!(
+        "\n## Streaks\n\n| Habit | Current (as of {}) | Longest in period |\n|---|--:|--:|\n",
+        as_of.format("%b %-d")
+    ));
+    for habit in &habits {
+        let habit_days = done_days(history, Some(habit.as_str()));
+        let period_days: BTreeSet<NaiveDate> = habit_days
+            .iter()
+            .filter(|day| period.contains(day))
+            .copied()
+            .collect();
+
+        markdown.push_str(&format!(
+            "| {} | {} | {} |\n",
+            escape_markdown(habit),
+            current_streak(&habit_days, as_of),
+            longest_streak(&period_days)
+        ));
+    }
+
+    markdown
+}
+
+/// Prints the Markdown report for a month or ISO week picked by the user and
+/// optionally saves it to a file.
+pub async fn export_markdown_report(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    values: &Vec<Vec<Value>>,
+    today: NaiveDate,
+) -> AppResult<()> {
+    let period = get_user_input_report_period(&valid_months(values), app_config.year, today)?;
+
+    // weeks can reach into last year's tab, so read every year
+    let history = get_year_histories(hub, app_config).await?;
+    let markdown = markdown_report(&history, &period, today);
+
+    println!();
+    println!("{}", markdown);
+
+    let mut is_save_selector = select("Save this report to a file?");
+    is_save_selector = is_save_selector.item(true, "yes", "");
+    is_save_selector = is_save_selector.item(false, "no", "");
+
+    if !is_save_selector.interact()? {
+        return Ok(());
+    }
+
+    let default_path = format!("habit-report-{}", period.label.replace(' ', "-"));
+    let path = PathBuf::from(get_user_input_export_path(&default_path)?).with_extension("md");
+    tokio::fs::write(&path, markdown).await?;
+    println!("📝 Saved {}", path.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn history(habits: &[(&str, &[(NaiveDate, bool)])]) -> CompletionHistory {
+        habits
+            .iter()
+            .map(|(habit, days)| (habit.to_string(), days.iter().copied().collect()))
+            .collect()
+    }
+
+    #[test]
+    fn week_report_has_rates_and_streaks() {
+        let history = history(&[
+            (
+                "Read",
+                &[
+                    (date(2024, 3, 3), true),
+                    (date(2024, 3, 4), true),
+                    (date(2024, 3, 5), true),
+                    (date(2024, 3, 6), false),
+                    (date(2024, 3, 7), true),
+                    (date(2024, 3, 8), false),
+                ],
+            ),
+            (
+                "Run",
+                &[(date(2024, 3, 4), false), (date(2024, 3, 5), true)],
+            ),
+        ]);
+        let today = date(2024, 3, 7);
+
+        let markdown = markdown_report(&history, &Period::week_of(today), today);
+
+        assert!(markdown.starts_with("# Habit report: 2024-W10\n\n"));
+        assert!(markdown.contains("| Habit | Mon 4 | Tue 5 |"));
+        assert!(markdown.contains("| Read | ✅ | ✅ | ❌ | ✅ |  |  |  | 3 |\n"));
+        assert!(markdown.contains("| Run | ❌ | ✅ |  |  |  |  |  | 1 |\n"));
+        assert!(markdown.contains("| **Total** | 1 | 2 | 0 | 1 | 0 | 0 | 0 | **4** |\n"));
+        assert!(markdown.contains("- **Total EXP:** 4\n- **Completion:** 67%\n"));
+        assert!(markdown.contains("- **Best:** Read (75%)\n- **Needs work:** Run (50%)\n"));
+        assert!(markdown.contains("| Habit | Current (as of Mar 7) | Longest in period |"));
+        assert!(markdown.contains("| Read | 1 | 2 |\n| Run | 0 | 1 |\n"));
+    }
+
+    #[test]
+    fn empty_period_says_nothing_was_tracked() {
+        let history = history(&[("Read", &[(date(2024, 2, 28), true)])]);
+        let today = date(2024, 3, 7);
+
+        for history in [CompletionHistory::new(), history] {
+            assert_eq!(
+                markdown_report(&history, &Period::month_of(today), today),
+                "# Habit report: March 2024\n\nNothing was tracked in this period.\n"
+            );
+        }
+    }
+}
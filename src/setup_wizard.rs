@@ -0,0 +1,426 @@
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+
+use chrono::{Datelike, Duration, FixedOffset, Utc};
+use cliclack::{input, select};
+use google_sheets4::{
+    Sheets,
+    api::{Sheet, Spreadsheet, SpreadsheetProperties},
+};
+use yup_oauth2::{
+    hyper::client::HttpConnector, hyper_rustls::HttpsConnector, read_service_account_key,
+};
+
+use crate::{
+    drive::share_spreadsheet,
+    error::{AppError, AppResult},
+    executor::{RetryPolicy, execute},
+    helpers::add_sheet_request,
+    init::{
+        AUDIT_LOG_SHEET_NAME, AppConfig, AuthMode, CONFIG_SHEET_NAME, DEFAULT_PROFILE, Profile,
+        app_config_dir, build_hub, client_secret_path, config_file_path, credentials_path,
+        ensure_sheet_ready, read_config_file, setup_authenticator, user_sheet_name,
+        write_config_file,
+    },
+    sheet_parser::remember_sheet_id,
+};
+
+pub async fn run_setup_wizard(profile_name: Option<&str>) -> AppResult<()> {
+    println!("🧭 Habit Tracker setup");
+    println!();
+
+    tokio::fs::create_dir_all(app_config_dir()).await?;
+
+    let mut config_file = read_config_file().await?.unwrap_or_default();
+
+    let profile_name: String = match profile_name {
+        Some(name) => name.to_string(),
+        None => input("Profile name:")
+            .default_input(DEFAULT_PROFILE)
+            .interact()?,
+    };
+
+    let timezone: String = input("Timezone (UTC offset):")
+        .default_input("+07:00")
+        .validate(|s: &String| {
+            if FixedOffset::from_str(s.trim()).is_ok() {
+                Ok(())
+            } else {
+                Err("Expected an offset like +07:00")
+            }
+        })
+        .interact()?;
+    let timezone = timezone.trim().to_string();
+
+    let utc_offset =
+        Duration::seconds(FixedOffset::from_str(&timezone).unwrap().local_minus_utc() as i64);
+
+    let mut auth_mode_selector = select("How should the tracker sign in to Google?");
+    auth_mode_selector = auth_mode_selector.item(
+        AuthMode::Oauth,
+        "👤 As me (browser login)",
+        "uses an OAuth client secret",
+    );
+    auth_mode_selector = auth_mode_selector.item(
+        AuthMode::ServiceAccount,
+        "🤖 As a service account",
+        "uses a service account key",
+    );
+
+    let auth_mode = auth_mode_selector.interact()?;
+
+    let default_creds_path = match auth_mode {
+        AuthMode::ServiceAccount => credentials_path(),
+        AuthMode::Oauth => client_secret_path(),
+    };
+    let creds_path = if profile_name == DEFAULT_PROFILE {
+        default_creds_path
+    } else {
+        let file_name = default_creds_path.file_name().unwrap().to_string_lossy();
+        app_config_dir().join(format!("{profile_name}-{file_name}"))
+    };
+
+    let share_with = match auth_mode {
+        AuthMode::ServiceAccount => {
+            let creds_path = locate_key_file(creds_path.clone(), "service account key").await?;
+            let secret = read_service_account_key(&creds_path).await.map_err(|err| {
+                AppError::Config(format!("failed to read service account key: {err}"))
+            })?;
+            Some(secret.client_email)
+        }
+        AuthMode::Oauth => {
+            locate_key_file(creds_path.clone(), "OAuth client secret").await?;
+            None
+        }
+    };
+
+    let user: String = input("Your name in a shared team spreadsheet:")
+        .placeholder("leave empty if the spreadsheet is only yours")
+        .required(false)
+        .validate(|s: &String| {
+            if s.trim()
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+            {
+                Ok(())
+            } else {
+                Err("Use letters, digits, '-' or '_', it becomes part of your tab names")
+            }
+        })
+        .interact()?;
+    let user = Some(user.trim().to_string()).filter(|user| !user.is_empty());
+
+    let team_members = match &user {
+        Some(user) => {
+            let team_members: String = input("Team members to summarize (comma-separated):")
+                .default_input(user)
+                .interact()?;
+            team_members
+                .split(',')
+                .map(|member| member.trim().to_string())
+                .filter(|member| !member.is_empty())
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    let year = (Utc::now() + utc_offset).year();
+    let mut app_config = AppConfig {
+        spreadsheet_id: String::new(),
+        year,
+        sheet_name: user_sheet_name(&year.to_string(), user.as_deref()),
+        config_sheet_name: user_sheet_name(CONFIG_SHEET_NAME, user.as_deref()),
+        audit_sheet_name: user_sheet_name(AUDIT_LOG_SHEET_NAME, user.as_deref()),
+        auth_mode,
+        profile_name: profile_name.clone(),
+        credentials_path: creds_path.clone(),
+        utc_offset,
+        user: user.clone(),
+        team_members: team_members.clone(),
+        leaderboard_profiles: Vec::new(),
+        calendar_patterns: BTreeMap::new(),
+        dry_run: false,
+    };
+
+    let auth = setup_authenticator(&app_config).await?;
+    let hub = build_hub(auth)?;
+
+    let mut source_selector = select("Where should your habits live?");
+    source_selector = source_selector.item(true, "📄 An existing spreadsheet", "");
+    source_selector = source_selector.item(false, "✨ Create a brand-new spreadsheet", "");
+
+    if source_selector.interact()? {
+        app_config.spreadsheet_id =
+            connect_existing_spreadsheet(&hub, share_with.as_deref()).await?;
+    } else {
+        create_spreadsheet(&hub, &mut app_config, share_with.as_deref()).await?;
+    }
+
+    config_file.profiles.insert(
+        profile_name.clone(),
+        Profile {
+            spreadsheet_id: app_config.spreadsheet_id,
+            auth_mode,
+            credentials_path: Some(creds_path),
+            timezone,
+            user,
+            team_members,
+            leaderboard_profiles: Vec::new(),
+            calendar_patterns: BTreeMap::new(),
+        },
+    );
+    if config_file.default_profile.is_none() {
+        config_file.default_profile = Some(profile_name.clone());
+    }
+
+    write_config_file(&config_file).await?;
+
+    println!(
+        "📝 Profile '{}' written to {}",
+        profile_name,
+        config_file_path().display()
+    );
+    println!();
+
+    Ok(())
+}
+
+async fn connect_existing_spreadsheet(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    share_with: Option<&str>,
+) -> AppResult<String> {
+    let spreadsheet_input: String = input("Spreadsheet URL or ID:")
+        .placeholder("https://docs.google.com/spreadsheets/d/<id>/edit")
+        .validate(|s: &String| {
+            if extract_spreadsheet_id(s).is_some() {
+                Ok(())
+            } else {
+                Err("Could not find a spreadsheet ID in that input")
+            }
+        })
+        .interact()?;
+
+    let spreadsheet_id = extract_spreadsheet_id(&spreadsheet_input).unwrap_or_default();
+
+    if let Some(client_email) = share_with {
+        println!();
+        println!("📨 One more step before we can read your tracker:");
+        println!("  1. Open the spreadsheet in your browser");
+        println!("  2. Click 'Share'");
+        println!("  3. Add {} as an Editor", client_email);
+        println!();
+    }
+
+    loop {
+        let spreadsheet = execute(
+            hub.spreadsheets()
+                .get(&spreadsheet_id)
+                .delegate(&mut RetryPolicy::default())
+                .doit(),
+        )
+        .await;
+
+        match spreadsheet {
+            Ok(spreadsheet) => {
+                let title = spreadsheet
+                    .properties
+                    .and_then(|p| p.title)
+                    .unwrap_or_default();
+                println!("✅ Connected to '{}'", title);
+                break;
+            }
+            Err(err) => {
+                eprintln!("Could not open the spreadsheet: {}", err);
+
+                let mut retry_selector = select("Check access and try again?");
+                retry_selector = retry_selector.item(true, "Retry 🔁", "");
+                retry_selector = retry_selector.item(false, "Abort 🚫", "");
+
+                if !retry_selector.interact()? {
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    Ok(spreadsheet_id)
+}
+
+async fn create_spreadsheet(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &mut AppConfig,
+    share_with: Option<&str>,
+) -> AppResult<()> {
+    let title: String = input("Spreadsheet title:")
+        .default_input("Habit Tracker")
+        .interact()?;
+
+    let wib = Utc::now() + app_config.utc_offset;
+
+    let year_sheet = add_sheet_request(&app_config.sheet_name, None, Some(0), 500, 32)
+        .add_sheet
+        .and_then(|request| request.properties);
+
+    // a service account owns what it creates, so someone has to be given access
+    let owner_email = match share_with {
+        Some(client_email) => {
+            println!(
+                "The spreadsheet will belong to {}, so it gets shared with you as an Editor.",
+                client_email
+            );
+            let owner_email: String = input("Your Google account email:")
+                .placeholder("you@gmail.com")
+                .validate(|s: &String| {
+                    if s.trim().contains('@') {
+                        Ok(())
+                    } else {
+                        Err("Expected an email address")
+                    }
+                })
+                .interact()?;
+            Some(owner_email.trim().to_string())
+        }
+        None => None,
+    };
+
+    let spreadsheet = Spreadsheet {
+        properties: Some(SpreadsheetProperties {
+            title: Some(title),
+            ..Default::default()
+        }),
+        sheets: Some(vec![Sheet {
+            properties: year_sheet,
+            ..Default::default()
+        }]),
+        ..Default::default()
+    };
+
+    let spreadsheet = execute(
+        hub.spreadsheets()
+            .create(spreadsheet)
+            .delegate(&mut RetryPolicy::default())
+            .doit(),
+    )
+    .await?;
+
+    app_config.spreadsheet_id = spreadsheet
+        .spreadsheet_id
+        .ok_or_else(|| AppError::SheetStructure(String::from("created spreadsheet has no id")))?;
+
+    println!("✨ Spreadsheet created: {}", app_config.spreadsheet_id);
+
+    if let Some(owner_email) = &owner_email {
+        share_spreadsheet(hub, &app_config.spreadsheet_id, owner_email).await?;
+        println!("📨 Shared with {}", owner_email);
+    }
+
+    for props in spreadsheet
+        .sheets
+        .iter()
+        .flatten()
+        .filter_map(|sheet| sheet.properties.as_ref())
+    {
+        if let (Some(title), Some(sheet_id)) = (&props.title, props.sheet_id) {
+            remember_sheet_id(&app_config.spreadsheet_id, title, sheet_id);
+        }
+    }
+
+    ensure_sheet_ready(hub, app_config, &wib).await?;
+
+    if let Some(url) = spreadsheet.spreadsheet_url {
+        println!();
+        println!("🔗 {}", url);
+    }
+    if owner_email.is_some() {
+        println!("It's listed under 'Shared with me' in your Drive.");
+    }
+    println!();
+
+    Ok(())
+}
+
+async fn locate_key_file(dest_path: PathBuf, label: &str) -> AppResult<PathBuf> {
+    if dest_path.exists() {
+        let mut keep_selector = select(format!("Found a {} at {}", label, dest_path.display()));
+        keep_selector = keep_selector.item(true, "Use it ✅", "");
+        keep_selector = keep_selector.item(false, "Replace it with another file", "");
+
+        if keep_selector.interact()? {
+            return Ok(dest_path);
+        }
+    }
+
+    let source: String = input(format!("Path to your {} (JSON):", label))
+        .placeholder("~/Downloads/my-project-123abc.json")
+        .validate(|s: &String| {
+            if expand_home(s.trim()).is_file() {
+                Ok(())
+            } else {
+                Err("File not found")
+            }
+        })
+        .interact()?;
+
+    tokio::fs::copy(expand_home(source.trim()), &dest_path).await?;
+
+    println!("🔑 Copied to {}", dest_path.display());
+
+    Ok(dest_path)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap().join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+pub fn extract_spreadsheet_id(input: &str) -> Option<String> {
+    let input = input.trim();
+
+    let id = match input.split_once("/spreadsheets/d/") {
+        Some((_, rest)) => rest.split(['/', '?', '#']).next().unwrap_or_default(),
+        None => input,
+    };
+
+    let is_valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if is_valid { Some(id.to_string()) } else { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "1BxiMVs0XRA5nFMdKvBdBZjgmUUqptlbs74OgvE2upms";
+
+    #[test]
+    fn extract_spreadsheet_id_reads_urls() {
+        let url = format!("https://docs.google.com/spreadsheets/d/{}/edit#gid=0", ID);
+        let shared = format!("https://docs.google.com/spreadsheets/d/{}?usp=sharing", ID);
+
+        assert_eq!(extract_spreadsheet_id(&url).as_deref(), Some(ID));
+        assert_eq!(extract_spreadsheet_id(&shared).as_deref(), Some(ID));
+    }
+
+    #[test]
+    fn extract_spreadsheet_id_takes_bare_ids() {
+        assert_eq!(
+            extract_spreadsheet_id(&format!("  {}\n", ID)).as_deref(),
+            Some(ID)
+        );
+    }
+
+    #[test]
+    fn extract_spreadsheet_id_rejects_junk() {
+        assert_eq!(extract_spreadsheet_id(""), None);
+        assert_eq!(extract_spreadsheet_id("my habits sheet"), None);
+        assert_eq!(extract_spreadsheet_id("https://example.com/?id=1"), None);
+        assert_eq!(
+            extract_spreadsheet_id("https://docs.google.com/spreadsheets/d//edit"),
+            None
+        );
+    }
+}
@@ -1,13 +1,23 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+    sync::{Mutex, MutexGuard},
+};
 
-use chrono::{DateTime, Datelike, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use google_sheets4::Sheets;
 use rand::{seq::SliceRandom, thread_rng};
 use serde_json::Value;
+use time::Month;
 use unicode_width::UnicodeWidthStr;
 use yup_oauth2::{hyper::client::HttpConnector, hyper_rustls::HttpsConnector};
 
-use crate::{AppConfig, init::valid_months, template_builder::generate_template_grid};
+use crate::{
+    AppConfig,
+    error::{AppError, AppResult},
+    executor::{RetryPolicy, execute},
+    init::valid_months,
+};
 
 pub fn get_active_habits(values: &Vec<Vec<Value>>, index: usize) -> HashMap<String, usize> {
     let mut habits: HashMap<String, usize> = HashMap::new();
@@ -29,8 +39,8 @@ pub fn get_active_habits(values: &Vec<Vec<Value>>, index: usize) -> HashMap<Stri
                 .get(config_table::Column::IsActive.as_usize_zero_based_index())
                 .and_then(|c| c.as_str());
 
-            let is_complete = is_complete.unwrap() == "TRUE";
-            let is_active = is_active.unwrap() == "TRUE";
+            let is_complete = is_complete.unwrap_or_default() == "TRUE";
+            let is_active = is_active.unwrap_or_default() == "TRUE";
 
             if !is_active || is_complete {
                 i += 1;
@@ -86,13 +96,11 @@ pub fn get_dates(values: &Vec<Vec<Value>>, index: usize) -> HashMap<usize, usize
     dates
 }
 
-pub async fn get_today_progresses(
-    values: &mut Vec<Vec<Value>>,
-    months: &mut HashMap<String, usize>,
+pub fn get_today_progresses(
+    values: &[Vec<Value>],
+    months: &HashMap<String, usize>,
     wib: &DateTime<Utc>,
-    hub: &Sheets<HttpsConnector<HttpConnector>>,
-    app_config: &AppConfig,
-) {
+) -> AppResult<()> {
     let messages = [
         "✅ You’ve completed {}! +1 EXP 🎯",
         "🔥 You nailed {}! +1 EXP",
@@ -102,21 +110,9 @@ pub async fn get_today_progresses(
     ];
 
     let current_month = wib.format("%B").to_string();
-    let mut row_index = if let Some(index) = months.get(&current_month) {
-        *index
-    } else {
-        println!(
-            "⚡ '{}' missing from database. Initiating reconstruction protocol... 🚧",
-            current_month
-        );
-
-        let (new_value, _) = generate_template_grid(hub, app_config, wib).await;
-        *values = new_value;
-        *months = valid_months(values);
-        *months
-            .get(&current_month)
-            .expect("Failed generating new month grid")
-    };
+    let mut row_index = *months
+        .get(&current_month)
+        .ok_or_else(|| AppError::SheetStructure(format!("'{}' grid not found", current_month)))?;
 
     let current_date = wib.day() as usize;
 
@@ -151,6 +147,8 @@ pub async fn get_today_progresses(
     } else {
         println!("No quests completed today. The world is waiting, hero ⚔️");
     }
+
+    Ok(())
 }
 
 pub fn print_activities(
@@ -179,7 +177,9 @@ pub fn print_activities(
         for (habit, _) in selected_habits {
             let date_index = dates.get(date).unwrap();
             let habit_index = habits.get(habit).unwrap();
-            let is_done = values[*habit_index].get(*date_index).unwrap() == "TRUE";
+            let is_done = values[*habit_index]
+                .get(*date_index)
+                .is_some_and(|cell| cell == "TRUE");
             let message = if is_done { "✅✅✅" } else { "❌❌❌" };
 
             let pad = width.saturating_sub(habit.width());
@@ -209,31 +209,111 @@ pub fn print_activities(
     );
 }
 
+/// Sheet ids per spreadsheet, filled by one `spreadsheets().get` and kept up
+/// to date as we add sheets, so later lookups don't cost a round trip.
+static SHEET_ID_CACHE: Mutex<BTreeMap<String, HashMap<String, i32>>> = Mutex::new(BTreeMap::new());
+
+pub async fn get_sheet_ids(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+) -> AppResult<HashMap<String, i32>> {
+    if let Some(sheet_ids) = lock_sheet_id_cache().get(&app_config.spreadsheet_id) {
+        return Ok(sheet_ids.clone());
+    }
+
+    let spreadsheet = execute(
+        hub.spreadsheets()
+            .get(&app_config.spreadsheet_id)
+            .param("fields", "sheets.properties(sheetId,title)")
+            .delegate(&mut RetryPolicy::default())
+            .doit(),
+    )
+    .await?;
+
+    let sheet_ids: HashMap<String, i32> = spreadsheet
+        .sheets
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|sheet| sheet.properties)
+        .filter_map(|props| Some((props.title?, props.sheet_id?)))
+        .collect();
+
+    lock_sheet_id_cache().insert(app_config.spreadsheet_id.clone(), sheet_ids.clone());
+
+    Ok(sheet_ids)
+}
+
+pub fn remember_sheet_id(spreadsheet_id: &str, sheet_name: &str, sheet_id: i32) {
+    lock_sheet_id_cache()
+        .entry(spreadsheet_id.to_string())
+        .or_default()
+        .insert(sheet_name.to_string(), sheet_id);
+}
+
+/// Drops the cached ids so the next lookup re-reads them, e.g. after a tab
+/// was renamed or deleted in the browser.
+pub fn forget_sheet_ids(spreadsheet_id: &str) {
+    lock_sheet_id_cache().remove(spreadsheet_id);
+}
+
+/// An id no existing sheet uses, so `addSheet` and follow-up requests can go in one batch.
+pub fn next_sheet_id(sheet_ids: &HashMap<String, i32>) -> i32 {
+    sheet_ids.values().max().map_or(1, |max| max + 1)
+}
+
+fn lock_sheet_id_cache() -> MutexGuard<'static, BTreeMap<String, HashMap<String, i32>>> {
+    SHEET_ID_CACHE.lock().unwrap_or_else(|err| err.into_inner())
+}
+
 pub async fn get_sheet_id(
     hub: &Sheets<HttpsConnector<HttpConnector>>,
     app_config: &AppConfig,
     sheet_name: &String,
-) -> i32 {
-    let (_, spreadsheet) = hub
-        .spreadsheets()
-        .get(&app_config.spreadsheet_id)
-        .doit()
-        .await
-        .unwrap();
-
-    let sheet_id = spreadsheet
-        .sheets
-        .as_ref()
-        .and_then(|sheets| {
-            sheets
-                .iter()
-                .filter_map(|sheet| sheet.properties.as_ref())
-                .find(|props| props.title.as_deref() == Some(sheet_name))
-                .and_then(|props| props.sheet_id)
-        })
-        .unwrap_or_else(|| panic!("Sheet id for {} not found!", sheet_name));
-
-    sheet_id
+) -> AppResult<i32> {
+    get_sheet_ids(hub, app_config)
+        .await?
+        .get(sheet_name)
+        .copied()
+        .ok_or_else(|| AppError::SheetStructure(format!("sheet '{}' not found", sheet_name)))
+}
+
+pub async fn get_sheet_titles(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+) -> AppResult<Vec<String>> {
+    Ok(get_sheet_ids(hub, app_config).await?.into_keys().collect())
+}
+
+/// Completion state of every habit on every date found in a year tab's month grids.
+pub type CompletionHistory = BTreeMap<String, BTreeMap<NaiveDate, bool>>;
+
+pub fn get_completion_history(values: &Vec<Vec<Value>>, year: i32) -> CompletionHistory {
+    let mut history = CompletionHistory::new();
+
+    for (month, index) in valid_months(values) {
+        let month = match Month::from_str(&month) {
+            Ok(month) => u8::from(month) as u32,
+            Err(_) => continue,
+        };
+
+        let habits = get_habits(values, index);
+        let dates = get_dates(values, index);
+
+        for (habit, row) in &habits {
+            let habit_history = history.entry(habit.to_string()).or_default();
+
+            for (day, col) in &dates {
+                let Some(date) = NaiveDate::from_ymd_opt(year, month, *day as u32) else {
+                    continue;
+                };
+
+                let is_done = values[*row].get(*col).is_some_and(|cell| cell == "TRUE");
+                habit_history.insert(date, is_done);
+            }
+        }
+    }
+
+    history
 }
 
 pub fn print_current_month_total_progress(values: &Vec<Vec<Value>>) {
@@ -243,7 +323,7 @@ pub fn print_current_month_total_progress(values: &Vec<Vec<Value>>) {
 
     for (habit, row) in habits {
         for (_, col) in &dates {
-            if values[row][*col] == "TRUE" {
+            if values[row].get(*col).is_some_and(|cell| cell == "TRUE") {
                 *habit_score.entry(habit.to_string()).or_insert(0) += 1;
             }
         }
@@ -335,6 +415,8 @@ fn random_element<'a>(list: &'a [&str]) -> &'a str {
 
 pub mod config_table {
     pub const START_ROW_INDEX: usize = 1;
+    /// Cells read from the Config sheet, the header plus up to 99 habits.
+    pub const RANGE: &str = "A1:C100";
 
     #[derive(Debug, Clone, Copy)]
     pub enum Column {
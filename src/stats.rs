@@ -0,0 +1,246 @@
+use std::collections::BTreeSet;
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::sheet_parser::CompletionHistory;
+
+#[derive(Clone)]
+pub struct Period {
+    pub label: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl Period {
+    pub fn week_of(date: NaiveDate) -> Period {
+        let start = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+        let week = date.iso_week();
+
+        Period {
+            label: format!("{}-W{:02}", week.year(), week.week()),
+            start,
+            end: start + Duration::days(6),
+        }
+    }
+
+    pub fn month_of(date: NaiveDate) -> Period {
+        let start = date.with_day(1).unwrap();
+        let next_month = if start.month() == 12 {
+            NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+        } else {
+            NaiveDate::from_ymd_opt(start.year(), start.month() + 1, 1)
+        };
+
+        Period {
+            label: start.format("%B %Y").to_string(),
+            start,
+            end: next_month.unwrap() - Duration::days(1),
+        }
+    }
+
+    pub fn quarter_of(date: NaiveDate) -> Period {
+        let quarter = (date.month() - 1) / 3;
+        let start = NaiveDate::from_ymd_opt(date.year(), quarter * 3 + 1, 1).unwrap();
+        let next_quarter = if quarter == 3 {
+            NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+        } else {
+            NaiveDate::from_ymd_opt(start.year(), start.month() + 3, 1)
+        };
+
+        Period {
+            label: format!("{}-Q{}", start.year(), quarter + 1),
+            start,
+            end: next_quarter.unwrap() - Duration::days(1),
+        }
+    }
+
+    pub fn year_of(date: NaiveDate) -> Period {
+        Period {
+            label: date.year().to_string(),
+            start: NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap(),
+            end: NaiveDate::from_ymd_opt(date.year(), 12, 31).unwrap(),
+        }
+    }
+
+    pub fn contains(&self, date: &NaiveDate) -> bool {
+        self.start <= *date && *date <= self.end
+    }
+}
+
+/// The day "this quarter" and "this year" refer to on `year`'s tab: `today`
+/// while tracking the current year, otherwise the nearest end of `year`.
+pub fn anchor_day(year: i32, today: NaiveDate) -> NaiveDate {
+    today.clamp(
+        NaiveDate::from_ymd_opt(year, 1, 1).unwrap(),
+        NaiveDate::from_ymd_opt(year, 12, 31).unwrap(),
+    )
+}
+
+pub fn merge_history(history: &mut CompletionHistory, other: CompletionHistory) {
+    for (habit, days) in other {
+        history.entry(habit).or_default().extend(days);
+    }
+}
+
+/// Dates on which `habit` (or any habit when `None`) was marked done.
+pub fn done_days(history: &CompletionHistory, habit: Option<&str>) -> BTreeSet<NaiveDate> {
+    history
+        .iter()
+        .filter(|(name, _)| habit.is_none_or(|habit| habit == name.as_str()))
+        .flat_map(|(_, days)| days.iter())
+        .filter(|(_, is_done)| **is_done)
+        .map(|(date, _)| *date)
+        .collect()
+}
+
+pub fn period_exp(history: &CompletionHistory, period: &Period) -> usize {
+    history
+        .values()
+        .flat_map(|days| days.iter())
+        .filter(|(date, is_done)| **is_done && period.contains(date))
+        .count()
+}
+
+/// Share of tracked habit-days in `period` (up to `today`) that were marked done.
+pub fn completion_rate(history: &CompletionHistory, period: &Period, today: NaiveDate) -> f64 {
+    let (done, total) = history
+        .values()
+        .flat_map(|days| days.iter())
+        .filter(|(date, _)| period.contains(date) && **date <= today)
+        .fold((0, 0), |(done, total), (_, is_done)| {
+            (done + *is_done as usize, total + 1)
+        });
+
+    if total == 0 {
+        0.0
+    } else {
+        done as f64 / total as f64
+    }
+}
+
+/// Consecutive done days ending at `as_of`, or the day before if `as_of` isn't done yet.
+pub fn current_streak(done_days: &BTreeSet<NaiveDate>, as_of: NaiveDate) -> usize {
+    let mut day = if done_days.contains(&as_of) {
+        as_of
+    } else {
+        as_of - Duration::days(1)
+    };
+
+    let mut streak = 0;
+    while done_days.contains(&day) {
+        streak += 1;
+        day -= Duration::days(1);
+    }
+
+    streak
+}
+
+pub fn longest_streak(done_days: &BTreeSet<NaiveDate>) -> usize {
+    let mut longest = 0;
+    let mut streak = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for day in done_days {
+        streak = match previous {
+            Some(previous) if *day - previous == Duration::days(1) => streak + 1,
+            _ => 1,
+        };
+        longest = longest.max(streak);
+        previous = Some(*day);
+    }
+
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn days(dates: &[NaiveDate]) -> BTreeSet<NaiveDate> {
+        dates.iter().copied().collect()
+    }
+
+    #[test]
+    fn current_streak_counts_back_from_today() {
+        let done = days(&[date(2024, 5, 1), date(2024, 5, 3), date(2024, 5, 4)]);
+
+        assert_eq!(current_streak(&done, date(2024, 5, 4)), 2);
+    }
+
+    #[test]
+    fn current_streak_starts_yesterday_when_today_is_open() {
+        let done = days(&[date(2024, 5, 2), date(2024, 5, 3)]);
+
+        assert_eq!(current_streak(&done, date(2024, 5, 4)), 2);
+        assert_eq!(current_streak(&done, date(2024, 5, 5)), 0);
+    }
+
+    #[test]
+    fn current_streak_crosses_year_boundary() {
+        let done = days(&[date(2023, 12, 30), date(2023, 12, 31), date(2024, 1, 1)]);
+
+        assert_eq!(current_streak(&done, date(2024, 1, 1)), 3);
+    }
+
+    #[test]
+    fn current_streak_crosses_leap_day() {
+        let done = days(&[date(2024, 2, 28), date(2024, 2, 29), date(2024, 3, 1)]);
+
+        assert_eq!(current_streak(&done, date(2024, 3, 1)), 3);
+    }
+
+    #[test]
+    fn quarter_of_covers_whole_quarter() {
+        let quarter = Period::quarter_of(date(2024, 2, 29));
+
+        assert_eq!(quarter.label, "2024-Q1");
+        assert_eq!(quarter.start, date(2024, 1, 1));
+        assert_eq!(quarter.end, date(2024, 3, 31));
+    }
+
+    #[test]
+    fn quarter_of_ends_last_quarter_on_new_years_eve() {
+        let quarter = Period::quarter_of(date(2025, 10, 1));
+
+        assert_eq!(quarter.label, "2025-Q4");
+        assert_eq!(quarter.start, date(2025, 10, 1));
+        assert_eq!(quarter.end, date(2025, 12, 31));
+    }
+
+    #[test]
+    fn anchor_day_stays_within_tracked_year() {
+        assert_eq!(anchor_day(2026, date(2026, 10, 19)), date(2026, 10, 19));
+        assert_eq!(anchor_day(2025, date(2026, 10, 19)), date(2025, 12, 31));
+        assert_eq!(anchor_day(2027, date(2026, 10, 19)), date(2027, 1, 1));
+    }
+
+    #[test]
+    fn longest_streak_picks_longest_run() {
+        let done = days(&[
+            date(2024, 5, 1),
+            date(2024, 5, 2),
+            date(2024, 5, 4),
+            date(2024, 5, 5),
+            date(2024, 5, 6),
+            date(2024, 5, 8),
+        ]);
+
+        assert_eq!(longest_streak(&done), 3);
+        assert_eq!(longest_streak(&BTreeSet::new()), 0);
+    }
+
+    #[test]
+    fn longest_streak_crosses_year_boundary_and_leap_day() {
+        let new_year = days(&[date(2023, 12, 31), date(2024, 1, 1), date(2024, 1, 2)]);
+        let leap_day = days(&[date(2024, 2, 28), date(2024, 2, 29), date(2024, 3, 1)]);
+        let no_leap_day = days(&[date(2023, 2, 27), date(2023, 2, 28), date(2023, 3, 1)]);
+
+        assert_eq!(longest_streak(&new_year), 3);
+        assert_eq!(longest_streak(&leap_day), 3);
+        assert_eq!(longest_streak(&no_leap_day), 3);
+    }
+}
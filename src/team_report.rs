@@ -0,0 +1,399 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Datelike, Utc};
+use google_sheets4::{
+    Sheets,
+    api::{ClearValuesRequest, ValueRange},
+};
+use serde_json::Value;
+use yup_oauth2::{hyper::client::HttpConnector, hyper_rustls::HttpsConnector};
+
+use crate::{
+    AppConfig,
+    data_updater::quote_sheet_name,
+    error::AppResult,
+    executor::{RetryPolicy, execute},
+    helpers::print_table,
+    init::{
+        LEADERBOARD_SHEET_NAME, TEAM_SHEET_NAME, build_hub, load_app_config, setup_authenticator,
+        user_sheet_name,
+    },
+    interaction::get_user_input_period,
+    sheet_parser::{CompletionHistory, get_completion_history, get_sheet_titles},
+    stats::{Period, completion_rate, current_streak, done_days, merge_history, period_exp},
+    template_builder::generate_sheet,
+};
+
+/// `period`'s years, and with `with_streaks` also the unbroken run of earlier
+/// years that have a tab, so a streak running into the period counts in full.
+fn history_years(period: &Period, with_streaks: bool, has_tab: impl Fn(i32) -> bool) -> Vec<i32> {
+    let mut first_year = period.start.year();
+    while with_streaks && has_tab(first_year - 1) {
+        first_year -= 1;
+    }
+
+    (first_year..=period.end.year()).collect()
+}
+
+/// Reads every team member's year tabs covering `period` from the shared spreadsheet.
+pub async fn get_team_histories(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    sheet_titles: &[String],
+    period: &Period,
+    with_streaks: bool,
+) -> AppResult<BTreeMap<String, CompletionHistory>> {
+    let mut member_sheets: Vec<(String, i32, String)> = Vec::new();
+    for member in &app_config.team_members {
+        let has_tab = |year: i32| {
+            let sheet_name = user_sheet_name(&year.to_string(), Some(member));
+            sheet_titles.contains(&sheet_name)
+        };
+
+        for year in history_years(period, with_streaks, has_tab) {
+            let sheet_name = user_sheet_name(&year.to_string(), Some(member));
+
+            if sheet_titles.contains(&sheet_name) {
+                member_sheets.push((member.clone(), year, sheet_name));
+            } else {
+                println!("{} has no '{}' tab yet, skipping.", member, sheet_name);
+            }
+        }
+    }
+
+    let mut member_histories: BTreeMap<String, CompletionHistory> = BTreeMap::new();
+    if member_sheets.is_empty() {
+        return Ok(member_histories);
+    }
+
+    let mut retry_policy = RetryPolicy::default();
+    let mut request = hub
+        .spreadsheets()
+        .values_batch_get(&app_config.spreadsheet_id)
+        .delegate(&mut retry_policy);
+    for (_, _, sheet_name) in &member_sheets {
+        request = request.add_ranges(&quote_sheet_name(sheet_name));
+    }
+
+    let response = execute(request.doit()).await?;
+    let value_ranges = response.value_ranges.unwrap_or_default();
+
+    for ((member, year, _), value_range) in member_sheets.into_iter().zip(value_ranges) {
+        let values = value_range.values.unwrap_or_default();
+        let history = get_completion_history(&values, year);
+
+        merge_history(member_histories.entry(member).or_default(), history);
+    }
+
+    Ok(member_histories)
+}
+
+/// Reads the year tabs covering `period` from each profile listed in `leaderboard_profiles`.
+pub async fn get_profile_histories(
+    app_config: &AppConfig,
+    period: &Period,
+    with_streaks: bool,
+) -> AppResult<BTreeMap<String, CompletionHistory>> {
+    let mut member_histories: BTreeMap<String, CompletionHistory> = BTreeMap::new();
+
+    for profile_name in &app_config.leaderboard_profiles {
+        let profile_config = load_app_config(Some(profile_name)).await?;
+        let member = profile_config
+            .user
+            .clone()
+            .unwrap_or_else(|| profile_name.clone());
+
+        let auth = setup_authenticator(&profile_config).await?;
+        let hub = build_hub(auth)?;
+        let sheet_titles = get_sheet_titles(&hub, &profile_config).await?;
+
+        let year_sheet_name =
+            |year: i32| user_sheet_name(&year.to_string(), profile_config.user.as_deref());
+        let has_tab = |year: i32| sheet_titles.contains(&year_sheet_name(year));
+
+        let history = member_histories.entry(member).or_default();
+        for year in history_years(period, with_streaks, has_tab) {
+            let sheet_name = year_sheet_name(year);
+            if !sheet_titles.contains(&sheet_name) {
+                continue;
+            }
+
+            let value_range = execute(
+                hub.spreadsheets()
+                    .values_get(
+                        &profile_config.spreadsheet_id,
+                        &quote_sheet_name(&sheet_name),
+                    )
+                    .delegate(&mut RetryPolicy::default())
+                    .doit(),
+            )
+            .await?;
+
+            let values = value_range.values.unwrap_or_default();
+            merge_history(history, get_completion_history(&values, year));
+        }
+    }
+
+    Ok(member_histories)
+}
+
+pub async fn print_team_summary(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    wib: &DateTime<Utc>,
+) -> AppResult<()> {
+    let period = Period::month_of(wib.date_naive());
+    let sheet_titles = get_sheet_titles(hub, app_config).await?;
+
+    let member_histories =
+        get_team_histories(hub, app_config, &sheet_titles, &period, false).await?;
+
+    if member_histories.is_empty() {
+        println!("No team data found for {}.", period.label);
+        return Ok(());
+    }
+
+    let habits: BTreeSet<&String> = member_histories
+        .values()
+        .flat_map(|history| history.keys())
+        .collect();
+
+    let habit_exp = |history: &CompletionHistory, habit: &String| -> usize {
+        history.get(habit).map_or(0, |days| {
+            days.iter()
+                .filter(|(date, is_done)| **is_done && period.contains(date))
+                .count()
+        })
+    };
+
+    let mut header: Vec<String> = vec![String::from("Member")];
+    header.extend(habits.iter().map(|habit| habit.to_string()));
+    header.push(String::from("Total EXP"));
+
+    let mut rows: Vec<Vec<String>> = vec![header];
+    for (member, history) in &member_histories {
+        let mut row = vec![member.clone()];
+        for habit in &habits {
+            row.push(habit_exp(history, habit).to_string());
+        }
+        row.push(period_exp(history, &period).to_string());
+        rows.push(row);
+    }
+
+    let mut team_total: Vec<String> = vec![String::from("Team")];
+    for habit in &habits {
+        let total: usize = member_histories
+            .values()
+            .map(|history| habit_exp(history, habit))
+            .sum();
+        team_total.push(total.to_string());
+    }
+    let total_exp: usize = member_histories
+        .values()
+        .map(|history| period_exp(history, &period))
+        .sum();
+    team_total.push(total_exp.to_string());
+    rows.push(team_total);
+
+    println!();
+    println!("👥 Guild report for {}:", period.label);
+    println!();
+    print_table(&rows);
+    println!();
+
+    write_report_sheet(
+        hub,
+        app_config,
+        &sheet_titles,
+        TEAM_SHEET_NAME,
+        &period.label,
+        rows,
+    )
+    .await
+}
+
+pub struct LeaderboardEntry {
+    pub member: String,
+    pub exp: usize,
+    pub completion_rate: f64,
+    pub streak: usize,
+}
+
+pub async fn print_leaderboard(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    wib: &DateTime<Utc>,
+) -> AppResult<()> {
+    let today = wib.date_naive();
+    let period = get_user_input_period(today)?;
+    let sheet_titles = get_sheet_titles(hub, app_config).await?;
+
+    let mut member_histories =
+        get_team_histories(hub, app_config, &sheet_titles, &period, true).await?;
+    for (member, history) in get_profile_histories(app_config, &period, true).await? {
+        merge_history(member_histories.entry(member).or_default(), history);
+    }
+
+    if member_histories.is_empty() {
+        println!(
+            "No members to rank. Add `team_members` or `leaderboard_profiles` to your profile."
+        );
+        return Ok(());
+    }
+
+    let as_of = period.end.min(today);
+    let mut entries: Vec<LeaderboardEntry> = member_histories
+        .iter()
+        .map(|(member, history)| LeaderboardEntry {
+            member: member.clone(),
+            exp: period_exp(history, &period),
+            completion_rate: completion_rate(history, &period, today),
+            streak: current_streak(&done_days(history, None), as_of),
+        })
+        .collect();
+
+    entries.sort_by(|a, b| {
+        b.exp
+            .cmp(&a.exp)
+            .then(b.completion_rate.total_cmp(&a.completion_rate))
+            .then(b.streak.cmp(&a.streak))
+    });
+
+    let medals = ["🥇", "🥈", "🥉"];
+
+    let mut rows: Vec<Vec<String>> = vec![vec![
+        String::from("Rank"),
+        String::from("Member"),
+        String::from("EXP"),
+        String::from("Completion"),
+        String::from("Streak"),
+    ]];
+    for (i, entry) in entries.iter().enumerate() {
+        let rank = match medals.get(i) {
+            Some(medal) => medal.to_string(),
+            None => (i + 1).to_string(),
+        };
+
+        rows.push(vec![
+            rank,
+            entry.member.clone(),
+            entry.exp.to_string(),
+            format!("{:.0}%", entry.completion_rate * 100.0),
+            format!("{} days", entry.streak),
+        ]);
+    }
+
+    println!();
+    println!("🏆 Leaderboard for {}:", period.label);
+    println!();
+    print_table(&rows);
+    println!();
+
+    if let Some(winner) = entries.first() {
+        println!("{} leads the guild with {} EXP!", winner.member, winner.exp);
+    }
+    for entry in entries.iter().filter(|entry| entry.exp == 0) {
+        println!(
+            "⏰ {} hasn't logged anything yet. Time to rally!",
+            entry.member
+        );
+    }
+    println!();
+
+    let title = format!("Leaderboard {}", period.label);
+    write_report_sheet(
+        hub,
+        app_config,
+        &sheet_titles,
+        LEADERBOARD_SHEET_NAME,
+        &title,
+        rows,
+    )
+    .await
+}
+
+async fn write_report_sheet(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    sheet_titles: &[String],
+    sheet_name: &str,
+    title: &str,
+    rows: Vec<Vec<String>>,
+) -> AppResult<()> {
+    if app_config.dry_run {
+        println!(
+            "🧪 Dry run, '{}' would be cleared and {} rows written.",
+            sheet_name,
+            rows.len() + 1
+        );
+        return Ok(());
+    }
+
+    if !sheet_titles.iter().any(|existing| existing == sheet_name) {
+        generate_sheet(hub, app_config, sheet_name, None).await?;
+    }
+
+    let mut sheet_values: Vec<Vec<Value>> = vec![vec![Value::String(title.to_string())]];
+    sheet_values.extend(
+        rows.into_iter()
+            .map(|row| row.into_iter().map(Value::String).collect::<Vec<Value>>()),
+    );
+
+    execute(
+        hub.spreadsheets()
+            .values_clear(
+                ClearValuesRequest::default(),
+                &app_config.spreadsheet_id,
+                &quote_sheet_name(sheet_name),
+            )
+            .delegate(&mut RetryPolicy::default())
+            .doit(),
+    )
+    .await?;
+
+    let range = format!("{}!A1", quote_sheet_name(sheet_name));
+    let value_range = ValueRange {
+        range: Some(range.clone()),
+        values: Some(sheet_values),
+        ..Default::default()
+    };
+
+    execute(
+        hub.spreadsheets()
+            .values_update(value_range, &app_config.spreadsheet_id, &range)
+            .value_input_option("USER_ENTERED")
+            .delegate(&mut RetryPolicy::default())
+            .doit(),
+    )
+    .await?;
+
+    println!("📊 '{}' sheet updated", sheet_name);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn january(year: i32) -> Period {
+        Period::month_of(NaiveDate::from_ymd_opt(year, 1, 1).unwrap())
+    }
+
+    #[test]
+    fn history_years_covers_only_the_period_without_streaks() {
+        assert_eq!(history_years(&january(2024), false, |_| true), vec![2024]);
+    }
+
+    #[test]
+    fn history_years_reaches_back_while_tabs_exist() {
+        let has_tab = |year: i32| (2021..=2024).contains(&year) && year != 2021;
+
+        assert_eq!(
+            history_years(&january(2024), true, has_tab),
+            vec![2022, 2023, 2024]
+        );
+    }
+}
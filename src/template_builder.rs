@@ -1,386 +1,276 @@
-use core::panic;
-
 use chrono::{DateTime, Datelike, Utc};
-use cliclack::input;
 use google_sheets4::{
     Sheets,
-    api::{BatchUpdateSpreadsheetRequest, BatchUpdateValuesRequest, ValueRange},
-    client,
+    api::{BatchUpdateSpreadsheetRequest, Request},
 };
 use serde_json::Value;
-use yup_oauth2::{
-    hyper::{self, client::HttpConnector},
-    hyper_rustls::HttpsConnector,
-};
+use yup_oauth2::{hyper::client::HttpConnector, hyper_rustls::HttpsConnector};
 
 use crate::{
     AppConfig,
-    data_updater::{get_cell_address, set_data},
+    error::AppResult,
+    executor::{RetryPolicy, execute},
     helpers::{
         add_sheet_request, auto_resize_dimension_request, clear_format_request,
         insert_rows_request, repeat_cell_request, set_data_validation_request,
+        update_cells_request,
     },
-    sheet_parser::{get_active_habits, get_sheet_id},
+    sheet_parser::{get_sheet_ids, next_sheet_id, remember_sheet_id},
 };
 
-pub async fn generate_template_grid(
-    hub: &Sheets<HttpsConnector<HttpConnector>>,
-    app_config: &AppConfig,
-    wib: &DateTime<Utc>,
-) -> (Vec<Vec<Value>>, i32) {
-    let sheet_id = get_sheet_id(hub, app_config, &app_config.sheet_name).await;
+/// Text color, background, font size and alignment of one part of a month grid.
+pub struct GridStyle {
+    pub fg: (f32, f32, f32),
+    pub bg: (f32, f32, f32),
+    pub font_size: i32,
+    pub horizontal_alignment: &'static str,
+}
 
-    let config_sheet = hub
-        .spreadsheets()
-        .values_get(&app_config.spreadsheet_id, "Config!A1:C100")
-        .doit()
-        .await;
+const CONFIG_COLUMNS: [&str; 3] = ["Habit", "IsComplete", "IsActive"];
 
-    let config_sheet = config_sheet.unwrap();
+pub const GRID_FONT_FAMILY: &str = "Arial";
 
-    let habits = get_active_habits(&config_sheet.1.values.unwrap(), 0);
-    let n_row: i32 = (habits.iter().count() + 2) as i32;
+/// The month name in the top-left corner of a grid.
+pub const MONTH_STYLE: GridStyle = GridStyle {
+    fg: (1.0, 1.0, 1.0),
+    bg: (0.0, 0.0, 0.0),
+    font_size: 10,
+    horizontal_alignment: "LEFT",
+};
 
-    {
-        let insert_rows = insert_rows_request(sheet_id, 0, n_row);
-
-        let clear_format = clear_format_request(sheet_id, 0, n_row);
-
-        let month_column_color = repeat_cell_request(
-            sheet_id,
-            0,
-            1,
-            0,
-            1,
-            (1.0, 1.0, 1.0),
-            (0.0, 0.0, 0.0),
-            10,
-            String::from("Arial"),
-            String::from("LEFT"),
-        );
-
-        let date_column_color = repeat_cell_request(
-            sheet_id,
-            0,
-            1,
-            1,
-            (wib.num_days_in_month() + 1) as i32,
-            (1.0, 1.0, 1.0),
-            (0.5, 1.5, 0.5),
-            9,
-            String::from("Arial"),
-            String::from("CENTER"),
-        );
-
-        let bool_format = repeat_cell_request(
-            sheet_id,
-            1,
-            n_row - 1,
-            1,
-            (wib.num_days_in_month() + 1) as i32,
-            (0.0, 0.0, 0.0),
-            (1.0, 1.0, 1.0),
-            9,
-            String::from("Arial"),
-            String::from("CENTER"),
-        );
-
-        let set_cell_data_type = set_data_validation_request(
-            sheet_id,
-            1,
-            n_row - 1,
-            1,
-            (wib.num_days_in_month() + 1) as i32,
-        );
-
-        let update_batch = BatchUpdateSpreadsheetRequest {
-            requests: Some(vec![
-                insert_rows,
-                clear_format,
-                set_cell_data_type,
-                month_column_color,
-                date_column_color,
-                bool_format,
-            ]),
-            include_spreadsheet_in_response: None,
-            response_include_grid_data: None,
-            response_ranges: None,
-        };
-
-        let _ = hub
-            .spreadsheets()
-            .batch_update(update_batch, &app_config.spreadsheet_id)
-            .doit()
-            .await;
-    }
+/// The day numbers along the top of a grid.
+pub const DATE_STYLE: GridStyle = GridStyle {
+    fg: (1.0, 1.0, 1.0),
+    bg: (0.5, 1.5, 0.5),
+    font_size: 9,
+    horizontal_alignment: "CENTER",
+};
 
-    let mut sorted_habit: Vec<_> = habits.keys().cloned().collect();
-    sorted_habit.sort();
+/// The checkboxes of each habit and day.
+pub const BOOL_STYLE: GridStyle = GridStyle {
+    fg: (0.0, 0.0, 0.0),
+    bg: (1.0, 1.0, 1.0),
+    font_size: 9,
+    horizontal_alignment: "CENTER",
+};
 
-    let mut updated_cell: Vec<ValueRange> = Vec::new();
+fn styled_cells_request(
+    sheet_id: i32,
+    rows: (i32, i32),
+    cols: (i32, i32),
+    style: &GridStyle,
+) -> Request {
+    repeat_cell_request(
+        sheet_id,
+        rows.0,
+        rows.1,
+        cols.0,
+        cols.1,
+        style.fg,
+        style.bg,
+        style.font_size,
+        String::from(GRID_FONT_FAMILY),
+        String::from(style.horizontal_alignment),
+    )
+}
 
-    let mut row_index: usize = 1;
+/// Builds every request needed to insert the grid of `wib`'s month at
+/// `start_row` of `sheet_id`, the top for the current month, along with the
+/// rows the grid will hold once they're applied.
+pub fn template_grid_requests(
+    sheet_id: i32,
+    start_row: i32,
+    wib: &DateTime<Utc>,
+    habits: &Vec<String>,
+) -> (Vec<Request>, Vec<Vec<Value>>) {
+    let n_row: i32 = (habits.iter().count() + 2) as i32;
+    let n_day = wib.num_days_in_month() as usize;
+    let end_row = start_row + n_row;
+    let day_cols = (1, (n_day + 1) as i32);
 
-    let current_month = wib.format("%B").to_string();
-    {
-        let update_value = String::from(format!("{current_month}"));
-        let cell_address = get_cell_address(row_index, 1);
-        set_data(
-            &mut updated_cell,
-            update_value,
-            cell_address,
-            &app_config.sheet_name,
-        );
-
-        row_index += 1;
-    }
+    let insert_rows = insert_rows_request(sheet_id, start_row, n_row);
 
-    for habit in &sorted_habit {
-        let update_value = String::from(format!("{habit}"));
-        let cell_address = get_cell_address(row_index, 1);
-        set_data(
-            &mut updated_cell,
-            update_value,
-            cell_address,
-            &app_config.sheet_name,
-        );
-
-        row_index += 1;
-    }
+    let clear_format = clear_format_request(sheet_id, start_row, end_row);
 
-    for i in 1..wib.num_days_in_month() + 1 {
-        let update_value = String::from(format!("{}", i));
-        let cell_address = get_cell_address(1, (i + 1) as usize);
-        set_data(
-            &mut updated_cell,
-            update_value,
-            cell_address,
-            &app_config.sheet_name,
-        );
-    }
+    let month_column_color =
+        styled_cells_request(sheet_id, (start_row, start_row + 1), (0, 1), &MONTH_STYLE);
 
-    let batch = BatchUpdateValuesRequest {
-        value_input_option: Some("USER_ENTERED".to_string()),
-        data: Some(updated_cell),
-        ..Default::default()
-    };
+    let date_column_color =
+        styled_cells_request(sheet_id, (start_row, start_row + 1), day_cols, &DATE_STYLE);
+
+    let bool_format = styled_cells_request(
+        sheet_id,
+        (start_row + 1, end_row - 1),
+        day_cols,
+        &BOOL_STYLE,
+    );
+
+    let set_cell_data_type =
+        set_data_validation_request(sheet_id, start_row + 1, end_row - 1, day_cols.0, day_cols.1);
 
-    let result = hub
-        .spreadsheets()
-        .values_batch_update(batch, &app_config.spreadsheet_id)
-        .doit()
-        .await;
-
-    match result {
-        Ok((_, response)) => {
-            println!(
-                "{} cells updated",
-                response.total_updated_cells.unwrap_or(0)
-            );
-        }
-        Err(err) => {
-            eprint!("Update failed: {:?}", err);
-        }
+    let mut rows: Vec<Vec<Value>> = Vec::new();
+    {
+        let mut month_row = vec![Value::String(wib.format("%B").to_string())];
+        month_row.extend((1..=n_day).map(|day| Value::String(day.to_string())));
+        rows.push(month_row);
     }
 
-    let sheet = hub
-        .spreadsheets()
-        .values_get(&app_config.spreadsheet_id, &app_config.sheet_name)
-        .doit()
-        .await;
-
-    let value_range = match sheet {
-        Ok((_, value_range)) => {
-            println!(
-                "✅ '{}' grid created successfully! You’re all set to continue. 🎉",
-                current_month
-            );
-            value_range
-                .values
-                .expect("Sheet value not found, make sure you have internet connection")
-        }
-        Err(_) => {
-            panic!(
-                "Generating new {} grid failed, make sure you have internet connection",
-                current_month
-            )
-        }
-    };
+    for habit in habits {
+        let mut habit_row = vec![Value::String(habit.clone())];
+        habit_row.extend((1..=n_day).map(|_| Value::String(String::from("FALSE"))));
+        rows.push(habit_row);
+    }
 
-    (value_range, sheet_id)
-}
+    // blank spacer row between months
+    rows.push(Vec::new());
 
-pub async fn generate_sheet(
-    hub: &Sheets<HttpsConnector<HttpConnector>>,
-    app_config: &AppConfig,
-    sheet_name: &str,
-    sheet_index: Option<i32>,
-) {
-    let create_new_sheet = add_sheet_request(sheet_name, sheet_index, 500, 32);
+    let set_values = update_cells_request(sheet_id, start_row, 0, &rows);
 
-    let update_batch = BatchUpdateSpreadsheetRequest {
-        requests: Some(vec![create_new_sheet]),
-        include_spreadsheet_in_response: None,
-        response_include_grid_data: None,
-        response_ranges: None,
-    };
+    let resize = auto_resize_dimension_request(sheet_id, "COLUMNS".to_string(), 0, 32);
 
-    let _ = hub
-        .spreadsheets()
-        .batch_update(update_batch, &app_config.spreadsheet_id)
-        .doit()
-        .await;
+    let requests = vec![
+        insert_rows,
+        clear_format,
+        set_cell_data_type,
+        month_column_color,
+        date_column_color,
+        bool_format,
+        set_values,
+        resize,
+    ];
+
+    (requests, rows)
 }
 
-pub async fn auto_resize_dimension(
-    hub: &Sheets<HttpsConnector<HttpConnector>>,
-    app_config: &AppConfig,
+/// Builds the requests that insert unchecked rows for `habits` at `start_row`
+/// of an existing month grid with `n_day` days, along with the rows they'll hold.
+pub fn habit_rows_requests(
     sheet_id: i32,
-) {
-    let resize = auto_resize_dimension_request(sheet_id, "COLUMNS".to_string(), 0, 32);
+    start_row: i32,
+    n_day: usize,
+    habits: &[String],
+) -> (Vec<Request>, Vec<Vec<Value>>) {
+    let end_row = start_row + habits.len() as i32;
+    let day_cols = (1, (n_day + 1) as i32);
+
+    let rows: Vec<Vec<Value>> = habits
+        .iter()
+        .map(|habit| {
+            let mut habit_row = vec![Value::String(habit.clone())];
+            habit_row.extend((1..=n_day).map(|_| Value::String(String::from("FALSE"))));
+            habit_row
+        })
+        .collect();
 
-    let update_batch = BatchUpdateSpreadsheetRequest {
-        requests: Some(vec![resize]),
-        include_spreadsheet_in_response: None,
-        response_include_grid_data: None,
-        response_ranges: None,
-    };
+    let requests = vec![
+        insert_rows_request(sheet_id, start_row, habits.len() as i32),
+        clear_format_request(sheet_id, start_row, end_row),
+        set_data_validation_request(sheet_id, start_row, end_row, day_cols.0, day_cols.1),
+        styled_cells_request(sheet_id, (start_row, end_row), day_cols, &BOOL_STYLE),
+        update_cells_request(sheet_id, start_row, 0, &rows),
+    ];
 
-    let _ = hub
-        .spreadsheets()
-        .batch_update(update_batch, &app_config.spreadsheet_id)
-        .doit()
-        .await;
+    (requests, rows)
 }
 
-pub async fn generate_config_sheet(
-    hub: &Sheets<HttpsConnector<HttpConnector>>,
-    app_config: &AppConfig,
-) -> client::Result<(hyper::Response<hyper::body::Body>, ValueRange)> {
-    let sheet_name = "Config";
-    generate_sheet(hub, app_config, sheet_name, None).await;
-
-    let user_inputs: String = input("Enter you habits (comma separated):")
-        .placeholder("e.g. reading, exercise, journaling")
-        .validate(|s: &String| {
-            if s.trim().is_empty() {
-                Err("Please enter at least one habit")
-            } else {
-                Ok(())
-            }
+/// Builds the requests that write `habits` as active Config rows from
+/// `start_row`, along with the rows they'll hold.
+pub fn config_habit_requests(
+    sheet_id: i32,
+    start_row: i32,
+    habits: &[String],
+) -> (Vec<Request>, Vec<Vec<Value>>) {
+    let rows: Vec<Vec<Value>> = habits
+        .iter()
+        .map(|habit| {
+            vec![
+                Value::String(habit.clone()),
+                Value::String(String::from("FALSE")),
+                Value::String(String::from("TRUE")),
+            ]
         })
-        .interact()
-        .unwrap();
-
-    let habits: Vec<String> = {
-        let mut inputs: Vec<String> = user_inputs
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
-
-        inputs.sort();
-        inputs
-    };
-
-    let mut updated_cell: Vec<ValueRange> = Vec::new();
-
-    let mut row_index = 1;
-    let column_count = {
-        let habit_table_column = vec!["Habit", "IsComplete", "IsActive"];
-        for (i, column) in habit_table_column.iter().enumerate() {
-            let update_value = String::from(format!("{column}"));
-            let cell_address = get_cell_address(row_index, i + 1);
-            set_data(
-                &mut updated_cell,
-                update_value,
-                cell_address,
-                &sheet_name.to_string(),
-            );
-        }
-        row_index += 1;
-        habit_table_column.iter().count()
-    };
+        .collect();
 
-    for habit in &habits {
-        let update_value = String::from(format!("{habit}"));
-        let cell_address = get_cell_address(row_index, 1);
-        set_data(
-            &mut updated_cell,
-            update_value,
-            cell_address,
-            &sheet_name.to_string(),
-        );
-
-        // set IsActive = true
-        let update_value = String::from("TRUE");
-        let cell_address = get_cell_address(row_index, 3);
-        set_data(
-            &mut updated_cell,
-            update_value,
-            cell_address,
-            &sheet_name.to_string(),
-        );
-
-        row_index += 1;
-    }
+    let set_values = update_cells_request(sheet_id, start_row, 0, &rows);
 
-    {
-        let batch = BatchUpdateValuesRequest {
-            value_input_option: Some("USER_ENTERED".to_string()),
-            data: Some(updated_cell),
-            ..Default::default()
-        };
-
-        let result = hub
-            .spreadsheets()
-            .values_batch_update(batch, &app_config.spreadsheet_id)
-            .doit()
-            .await;
-
-        match result {
-            Ok((_, response)) => {
-                println!(
-                    "{} cells updated",
-                    response.total_updated_cells.unwrap_or(0)
-                );
-            }
-            Err(err) => {
-                eprint!("Update failed: {:?}", err);
-            }
-        }
-    }
-
-    let sheet_id = get_sheet_id(hub, app_config, &"Config".to_string()).await;
     let bool_format = set_data_validation_request(
         sheet_id,
+        start_row,
+        start_row + habits.len() as i32,
         1,
-        (habits.iter().count() + 1) as i32,
-        1,
-        column_count as i32,
+        CONFIG_COLUMNS.len() as i32,
     );
 
+    (vec![set_values, bool_format], rows)
+}
+
+/// Builds the requests that add a Config sheet listing `habits` as active,
+/// along with the rows the sheet will hold once they're applied.
+pub fn config_sheet_requests(
+    sheet_name: &str,
+    sheet_id: i32,
+    habits: &[String],
+) -> (Vec<Request>, Vec<Vec<Value>>) {
+    let create_new_sheet = add_sheet_request(sheet_name, Some(sheet_id), None, 500, 32);
+
+    let column_count = CONFIG_COLUMNS.len();
+
+    let mut rows: Vec<Vec<Value>> = vec![
+        CONFIG_COLUMNS
+            .iter()
+            .map(|column| Value::String(column.to_string()))
+            .collect(),
+    ];
+
+    let set_header = update_cells_request(sheet_id, 0, 0, &rows);
+
+    let (habit_requests, mut habit_rows) = config_habit_requests(sheet_id, 1, habits);
+    rows.append(&mut habit_rows);
+
     let resize =
         auto_resize_dimension_request(sheet_id, "COLUMNS".to_string(), 0, column_count as i32);
 
+    let mut requests = vec![create_new_sheet, set_header];
+    requests.extend(habit_requests);
+    requests.push(resize);
+
+    (requests, rows)
+}
+
+pub async fn generate_sheet(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    sheet_name: &str,
+    sheet_index: Option<i32>,
+) -> AppResult<i32> {
+    let sheet_id = next_sheet_id(&get_sheet_ids(hub, app_config).await?);
+    let create_new_sheet = add_sheet_request(sheet_name, Some(sheet_id), sheet_index, 500, 32);
+
+    send_batch_update(hub, app_config, vec![create_new_sheet]).await?;
+    remember_sheet_id(&app_config.spreadsheet_id, sheet_name, sheet_id);
+
+    Ok(sheet_id)
+}
+
+pub async fn send_batch_update(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    requests: Vec<Request>,
+) -> AppResult<()> {
     let update_batch = BatchUpdateSpreadsheetRequest {
-        requests: Some(vec![bool_format, resize]),
+        requests: Some(requests),
         include_spreadsheet_in_response: None,
         response_include_grid_data: None,
         response_ranges: None,
     };
 
-    let _ = hub
-        .spreadsheets()
-        .batch_update(update_batch, &app_config.spreadsheet_id)
-        .doit()
-        .await;
-
-    let sheet = hub
-        .spreadsheets()
-        .values_get(&app_config.spreadsheet_id, "Config!A1:C100")
-        .doit()
-        .await;
+    execute(
+        hub.spreadsheets()
+            .batch_update(update_batch, &app_config.spreadsheet_id)
+            .delegate(&mut RetryPolicy::default())
+            .doit(),
+    )
+    .await?;
 
-    sheet
+    Ok(())
 }
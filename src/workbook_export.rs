@@ -0,0 +1,643 @@
+use std::path::PathBuf;
+
+use google_sheets4::Sheets;
+use serde_json::Value;
+use yup_oauth2::{hyper::client::HttpConnector, hyper_rustls::HttpsConnector};
+
+use crate::{
+    AppConfig,
+    cli::ExportFormat,
+    data_updater::quote_sheet_name,
+    error::AppResult,
+    executor::{RetryPolicy, execute},
+    heatmap_export::{crc32, escape_xml},
+    html_report::get_year_sheets,
+    init::valid_months,
+    sheet_parser::{get_dates, get_habits},
+    template_builder::{BOOL_STYLE, DATE_STYLE, GRID_FONT_FAMILY, GridStyle, MONTH_STYLE},
+};
+
+const ODS_MIMETYPE: &str = "application/vnd.oasis.opendocument.spreadsheet";
+/// Width of the day columns, in characters.
+const DAY_COLUMN_WIDTH: usize = 4;
+
+#[derive(Clone, Copy, PartialEq)]
+enum CellStyle {
+    Plain,
+    Month,
+    Date,
+    Bool,
+}
+
+impl CellStyle {
+    /// Styles in the order of their XLSX `cellXfs` index, after the default one.
+    const STYLED: [CellStyle; 3] = [CellStyle::Month, CellStyle::Date, CellStyle::Bool];
+
+    fn grid_style(self) -> Option<&'static GridStyle> {
+        match self {
+            CellStyle::Plain => None,
+            CellStyle::Month => Some(&MONTH_STYLE),
+            CellStyle::Date => Some(&DATE_STYLE),
+            CellStyle::Bool => Some(&BOOL_STYLE),
+        }
+    }
+
+    fn xlsx_index(self) -> usize {
+        CellStyle::STYLED
+            .iter()
+            .position(|style| *style == self)
+            .map_or(0, |i| i + 1)
+    }
+
+    fn ods_name(self) -> &'static str {
+        match self {
+            CellStyle::Plain => "plain",
+            CellStyle::Month => "month",
+            CellStyle::Date => "date",
+            CellStyle::Bool => "check",
+        }
+    }
+}
+
+enum CellValue {
+    Empty,
+    Text(String),
+    Bool(bool),
+}
+
+struct Cell {
+    value: CellValue,
+    style: CellStyle,
+}
+
+struct WorkbookSheet {
+    name: String,
+    rows: Vec<Vec<Cell>>,
+    /// Width of the habit name column, in characters.
+    first_column_width: usize,
+}
+
+fn cell_value(value: &Value) -> CellValue {
+    match value.as_str().unwrap_or_default() {
+        "" => CellValue::Empty,
+        "TRUE" => CellValue::Bool(true),
+        "FALSE" => CellValue::Bool(false),
+        text => CellValue::Text(text.to_string()),
+    }
+}
+
+/// Turns sheet values into cells, styling month grids the way
+/// `template_grid_requests` does when `is_year_tab`.
+fn to_workbook_sheet(name: &str, values: &Vec<Vec<Value>>, is_year_tab: bool) -> WorkbookSheet {
+    let mut styles: Vec<Vec<CellStyle>> = values
+        .iter()
+        .map(|row| vec![CellStyle::Plain; row.len()])
+        .collect();
+
+    if is_year_tab {
+        for (_, index) in valid_months(values) {
+            let dates = get_dates(values, index);
+
+            styles[index - 1][0] = CellStyle::Month;
+            for col in dates.values() {
+                styles[index - 1][*col] = CellStyle::Date;
+            }
+            for row in get_habits(values, index).values() {
+                for col in dates.values() {
+                    if let Some(style) = styles[*row].get_mut(*col) {
+                        *style = CellStyle::Bool;
+                    }
+                }
+            }
+        }
+    }
+
+    let rows = values
+        .iter()
+        .zip(styles)
+        .map(|(row, row_styles)| {
+            row.iter()
+                .zip(row_styles)
+                .map(|(value, style)| Cell {
+                    value: cell_value(value),
+                    style,
+                })
+                .collect()
+        })
+        .collect();
+
+    let first_column_width = values
+        .iter()
+        .filter_map(|row| row.first().and_then(|cell| cell.as_str()))
+        .map(|text| text.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(DAY_COLUMN_WIDTH)
+        + 2;
+
+    WorkbookSheet {
+        name: name.to_string(),
+        rows,
+        first_column_width,
+    }
+}
+
+fn hex_color(rgb: (f32, f32, f32)) -> String {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "{:02X}{:02X}{:02X}",
+        channel(rgb.0),
+        channel(rgb.1),
+        channel(rgb.2)
+    )
+}
+
+/// Spreadsheet column letters for a zero-based column, e.g. 27 is `AB`.
+fn column_name(col: usize) -> String {
+    let mut name = String::new();
+    let mut n = col + 1;
+    while n > 0 {
+        name.insert(0, (b'A' + ((n - 1) % 26) as u8) as char);
+        n = (n - 1) / 26;
+    }
+    name
+}
+
+fn xlsx_styles() -> String {
+    let mut fonts = format!(
+        "<font><sz val=\"10\"/><name val=\"{}\"/></font>",
+        GRID_FONT_FAMILY
+    );
+    // the first two fills are reserved by Excel
+    let mut fills = String::from(
+        "<fill><patternFill patternType=\"none\"/></fill>\
+         <fill><patternFill patternType=\"gray125\"/></fill>",
+    );
+    let mut xfs = String::from("<xf numFmtId=\"0\" fontId=\"0\" fillId=\"0\" borderId=\"0\"/>");
+
+    for (i, style) in CellStyle::STYLED.iter().enumerate() {
+        let grid_style = style.grid_style().unwrap();
+        fonts.push_str(&format!(
+            "<font><sz val=\"{}\"/><color rgb=\"FF{}\"/><name val=\"{}\"/></font>",
+            grid_style.font_size,
+            hex_color(grid_style.fg),
+            GRID_FONT_FAMILY
+        ));
+        fills.push_str(&format!(
+            "<fill><patternFill patternType=\"solid\"><fgColor rgb=\"FF{}\"/></patternFill></fill>",
+            hex_color(grid_style.bg)
+        ));
+        xfs.push_str(&format!(
+            "<xf numFmtId=\"0\" fontId=\"{0}\" fillId=\"{1}\" borderId=\"0\" applyFont=\"1\" \
+             applyFill=\"1\" applyAlignment=\"1\"><alignment horizontal=\"{2}\"/></xf>",
+            i + 1,
+            i + 2,
+            grid_style.horizontal_alignment.to_lowercase()
+        ));
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <styleSheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\">\
+         <fonts count=\"{}\">{}</fonts><fills count=\"{}\">{}</fills>\
+         <borders count=\"1\"><border/></borders>\
+         <cellStyleXfs count=\"1\"><xf numFmtId=\"0\" fontId=\"0\" fillId=\"0\" borderId=\"0\"/>\
+         </cellStyleXfs><cellXfs count=\"{}\">{}</cellXfs></styleSheet>",
+        CellStyle::STYLED.len() + 1,
+        fonts,
+        CellStyle::STYLED.len() + 2,
+        fills,
+        CellStyle::STYLED.len() + 1,
+        xfs
+    )
+}
+
+fn xlsx_worksheet(sheet: &WorkbookSheet) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <worksheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\">\
+         <cols><col min=\"1\" max=\"1\" width=\"{}\" customWidth=\"1\"/>\
+         <col min=\"2\" max=\"32\" width=\"{}\" customWidth=\"1\"/></cols><sheetData>",
+        sheet.first_column_width, DAY_COLUMN_WIDTH
+    );
+
+    for (row, cells) in sheet.rows.iter().enumerate() {
+        xml.push_str(&format!("<row r=\"{}\">", row + 1));
+        for (col, cell) in cells.iter().enumerate() {
+            let reference = format!("{}{}", column_name(col), row + 1);
+            let style = cell.style.xlsx_index();
+            match &cell.value {
+                CellValue::Empty if style == 0 => {}
+                CellValue::Empty => {
+                    xml.push_str(&format!("<c r=\"{}\" s=\"{}\"/>", reference, style))
+                }
+                CellValue::Text(text) => xml.push_str(&format!(
+                    "<c r=\"{}\" s=\"{}\" t=\"inlineStr\"><is><t>{}</t></is></c>",
+                    reference,
+                    style,
+                    escape_xml(text)
+                )),
+                CellValue::Bool(value) => xml.push_str(&format!(
+                    "<c r=\"{}\" s=\"{}\" t=\"b\"><v>{}</v></c>",
+                    reference, style, *value as u8
+                )),
+            }
+        }
+        xml.push_str("</row>");
+    }
+
+    xml.push_str("</sheetData></worksheet>");
+    xml
+}
+
+fn xlsx_files(sheets: &[WorkbookSheet]) -> Vec<(String, Vec<u8>)> {
+    let mut content_types = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+         <Default Extension=\"rels\" \
+         ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+         <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+         <Override PartName=\"/xl/workbook.xml\" ContentType=\"application/\
+         vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml\"/>\
+         <Override PartName=\"/xl/styles.xml\" ContentType=\"application/\
+         vnd.openxmlformats-officedocument.spreadsheetml.styles+xml\"/>",
+    );
+    let mut workbook = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <workbook xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" \
+         xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\">\
+         <sheets>",
+    );
+    let mut workbook_rels = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+         <Relationship Id=\"rStyles\" Type=\"http://schemas.openxmlformats.org/officeDocument/\
+         2006/relationships/styles\" Target=\"styles.xml\"/>",
+    );
+
+    let mut files = Vec::new();
+    for (i, sheet) in sheets.iter().enumerate() {
+        let n = i + 1;
+        content_types.push_str(&format!(
+            "<Override PartName=\"/xl/worksheets/sheet{}.xml\" ContentType=\"application/\
+             vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml\"/>",
+            n
+        ));
+        workbook.push_str(&format!(
+            "<sheet name=\"{}\" sheetId=\"{}\" r:id=\"rSheet{}\"/>",
+            escape_xml(&sheet.name),
+            n,
+            n
+        ));
+        workbook_rels.push_str(&format!(
+            "<Relationship Id=\"rSheet{}\" Type=\"http://schemas.openxmlformats.org/\
+             officeDocument/2006/relationships/worksheet\" Target=\"worksheets/sheet{}.xml\"/>",
+            n, n
+        ));
+        files.push((
+            format!("xl/worksheets/sheet{}.xml", n),
+            xlsx_worksheet(sheet).into_bytes(),
+        ));
+    }
+    content_types.push_str("</Types>");
+    workbook.push_str("</sheets></workbook>");
+    workbook_rels.push_str("</Relationships>");
+
+    let root_rels = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+         <Relationship Id=\"rWorkbook\" Type=\"http://schemas.openxmlformats.org/officeDocument/\
+         2006/relationships/officeDocument\" Target=\"xl/workbook.xml\"/></Relationships>",
+    );
+
+    let mut package = vec![
+        (
+            String::from("[Content_Types].xml"),
+            content_types.into_bytes(),
+        ),
+        (String::from("_rels/.rels"), root_rels.into_bytes()),
+        (String::from("xl/workbook.xml"), workbook.into_bytes()),
+        (
+            String::from("xl/_rels/workbook.xml.rels"),
+            workbook_rels.into_bytes(),
+        ),
+        (String::from("xl/styles.xml"), xlsx_styles().into_bytes()),
+    ];
+    package.extend(files);
+    package
+}
+
+/// Column width in centimeters for `chars` characters of a 10pt font.
+fn ods_width(chars: usize) -> f64 {
+    chars as f64 * 0.2
+}
+
+fn ods_styles(sheets: &[WorkbookSheet]) -> String {
+    // each sheet sizes its first column to its own habit names
+    let mut xml = String::new();
+    for (i, sheet) in sheets.iter().enumerate() {
+        xml.push_str(&format!(
+            "<style:style style:name=\"first{}\" style:family=\"table-column\">\
+             <style:table-column-properties style:column-width=\"{:.2}cm\"/></style:style>",
+            i,
+            ods_width(sheet.first_column_width)
+        ));
+    }
+
+    xml.push_str(&format!(
+        "<style:style style:name=\"day\" style:family=\"table-column\">\
+         <style:table-column-properties style:column-width=\"{:.2}cm\"/></style:style>\
+         <style:style style:name=\"plain\" style:family=\"table-cell\">\
+         <style:text-properties style:font-name=\"{1}\" fo:font-family=\"{1}\" \
+         fo:font-size=\"10pt\"/></style:style>",
+        ods_width(DAY_COLUMN_WIDTH),
+        GRID_FONT_FAMILY
+    ));
+
+    for style in CellStyle::STYLED {
+        let grid_style = style.grid_style().unwrap();
+        let align = match grid_style.horizontal_alignment {
+            "CENTER" => "center",
+            "RIGHT" => "end",
+            _ => "start",
+        };
+        xml.push_str(&format!(
+            "<style:style style:name=\"{}\" style:family=\"table-cell\">\
+             <style:table-cell-properties fo:background-color=\"#{}\"/>\
+             <style:paragraph-properties fo:text-align=\"{}\"/>\
+             <style:text-properties fo:color=\"#{}\" style:font-name=\"{4}\" \
+             fo:font-family=\"{4}\" fo:font-size=\"{5}pt\"/></style:style>",
+            style.ods_name(),
+            hex_color(grid_style.bg),
+            align,
+            hex_color(grid_style.fg),
+            GRID_FONT_FAMILY,
+            grid_style.font_size
+        ));
+    }
+
+    xml
+}
+
+fn ods_content(sheets: &[WorkbookSheet]) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <office:document-content \
+         xmlns:office=\"urn:oasis:names:tc:opendocument:xmlns:office:1.0\" \
+         xmlns:style=\"urn:oasis:names:tc:opendocument:xmlns:style:1.0\" \
+         xmlns:text=\"urn:oasis:names:tc:opendocument:xmlns:text:1.0\" \
+         xmlns:table=\"urn:oasis:names:tc:opendocument:xmlns:table:1.0\" \
+         xmlns:fo=\"urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0\" \
+         office:version=\"1.2\"><office:font-face-decls>\
+         <style:font-face style:name=\"{0}\" svg:font-family=\"{0}\" \
+         xmlns:svg=\"urn:oasis:names:tc:opendocument:xmlns:svg-compatible:1.0\"/>\
+         </office:font-face-decls><office:automatic-styles>",
+        GRID_FONT_FAMILY
+    );
+
+    xml.push_str(&ods_styles(sheets));
+    xml.push_str("</office:automatic-styles><office:body><office:spreadsheet>");
+
+    for (i, sheet) in sheets.iter().enumerate() {
+        xml.push_str(&format!(
+            "<table:table table:name=\"{}\">\
+             <table:table-column table:style-name=\"first{}\" \
+             table:default-cell-style-name=\"plain\"/>\
+             <table:table-column table:style-name=\"day\" table:number-columns-repeated=\"31\" \
+             table:default-cell-style-name=\"plain\"/>",
+            escape_xml(&sheet.name),
+            i
+        ));
+
+        for cells in &sheet.rows {
+            xml.push_str("<table:table-row>");
+            if cells.is_empty() {
+                xml.push_str("<table:table-cell/>");
+            }
+            for cell in cells {
+                let style = cell.style.ods_name();
+                match &cell.value {
+                    CellValue::Empty => xml.push_str(&format!(
+                        "<table:table-cell table:style-name=\"{}\"/>",
+                        style
+                    )),
+                    CellValue::Text(text) => xml.push_str(&format!(
+                        "<table:table-cell table:style-name=\"{}\" office:value-type=\"string\">\
+                         <text:p>{}</text:p></table:table-cell>",
+                        style,
+                        escape_xml(text)
+                    )),
+                    CellValue::Bool(value) => xml.push_str(&format!(
+                        "<table:table-cell table:style-name=\"{}\" office:value-type=\"boolean\" \
+                         office:boolean-value=\"{}\"><text:p>{}</text:p></table:table-cell>",
+                        style,
+                        value,
+                        if *value { "TRUE" } else { "FALSE" }
+                    )),
+                }
+            }
+            xml.push_str("</table:table-row>");
+        }
+
+        xml.push_str("</table:table>");
+    }
+
+    xml.push_str("</office:spreadsheet></office:body></office:document-content>");
+    xml
+}
+
+fn ods_files(sheets: &[WorkbookSheet]) -> Vec<(String, Vec<u8>)> {
+    let manifest = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <manifest:manifest xmlns:manifest=\"urn:oasis:names:tc:opendocument:xmlns:manifest:1.0\" \
+         manifest:version=\"1.2\">\
+         <manifest:file-entry manifest:full-path=\"/\" manifest:version=\"1.2\" \
+         manifest:media-type=\"{}\"/>\
+         <manifest:file-entry manifest:full-path=\"content.xml\" \
+         manifest:media-type=\"text/xml\"/></manifest:manifest>",
+        ODS_MIMETYPE
+    );
+
+    // the mimetype has to come first so file managers can sniff it
+    vec![
+        (String::from("mimetype"), ODS_MIMETYPE.as_bytes().to_vec()),
+        (String::from("META-INF/manifest.xml"), manifest.into_bytes()),
+        (
+            String::from("content.xml"),
+            ods_content(sheets).into_bytes(),
+        ),
+    ]
+}
+
+/// A zip archive of `files`. The ODS `mimetype` entry is stored as is, as the
+/// format requires, everything else is deflated.
+fn zip_archive(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut central_directory = Vec::new();
+
+    for (name, data) in files {
+        let is_stored = name == "mimetype";
+        let compressed = if is_stored {
+            data.clone()
+        } else {
+            miniz_oxide::deflate::compress_to_vec(data, 6)
+        };
+        let method: u16 = if is_stored { 0 } else { 8 };
+        let crc = crc32(data);
+        let offset = archive.len() as u32;
+
+        // version needed, flags, method, time, date (1980-01-01), crc, sizes, name length
+        let mut header = Vec::new();
+        header.extend_from_slice(&20u16.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&method.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&0x21u16.to_le_bytes());
+        header.extend_from_slice(&crc.to_le_bytes());
+        header.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+
+        archive.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(&compressed);
+
+        central_directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        central_directory.extend_from_slice(&20u16.to_le_bytes());
+        central_directory.extend_from_slice(&header);
+        // comment length, disk number, internal and external attributes
+        central_directory.extend_from_slice(&[0; 10]);
+        central_directory.extend_from_slice(&offset.to_le_bytes());
+        central_directory.extend_from_slice(name.as_bytes());
+    }
+
+    let directory_offset = archive.len() as u32;
+    archive.extend_from_slice(&central_directory);
+    archive.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    archive.extend_from_slice(&[0; 4]);
+    archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&directory_offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes());
+
+    archive
+}
+
+/// Writes the Config sheet and every year tab to a local `.xlsx` or `.ods`
+/// workbook, with month grids colored like the online tracker.
+pub async fn export_workbook(
+    hub: &Sheets<HttpsConnector<HttpConnector>>,
+    app_config: &AppConfig,
+    format: ExportFormat,
+    output: Option<PathBuf>,
+) -> AppResult<()> {
+    let year_sheets = get_year_sheets(hub, app_config).await?;
+
+    let mut sheet_names = vec![app_config.config_sheet_name.clone()];
+    sheet_names.extend(year_sheets.into_iter().map(|(_, sheet_name)| sheet_name));
+
+    let mut retry_policy = RetryPolicy::default();
+    let mut request = hub
+        .spreadsheets()
+        .values_batch_get(&app_config.spreadsheet_id)
+        .delegate(&mut retry_policy);
+    for sheet_name in &sheet_names {
+        request = request.add_ranges(&quote_sheet_name(sheet_name));
+    }
+
+    let response = execute(request.doit()).await?;
+    let sheets: Vec<WorkbookSheet> = sheet_names
+        .iter()
+        .zip(response.value_ranges.unwrap_or_default())
+        .enumerate()
+        .map(|(i, (sheet_name, value_range))| {
+            let values = value_range.values.unwrap_or_default();
+            to_workbook_sheet(sheet_name, &values, i > 0)
+        })
+        .collect();
+
+    let files = match format {
+        ExportFormat::Xlsx => xlsx_files(&sheets),
+        ExportFormat::Ods => ods_files(&sheets),
+        ExportFormat::Csv | ExportFormat::Json | ExportFormat::Ics => {
+            unreachable!("Long-format exports are written by export_history")
+        }
+    };
+
+    let path = output.unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "habit-tracker-{}.{}",
+            app_config.profile_name,
+            format.extension()
+        ))
+    });
+    tokio::fs::write(&path, zip_archive(&files)).await?;
+
+    println!("📒 Saved {} sheets to {}", sheets.len(), path.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u16_at(bytes: &[u8], at: usize) -> usize {
+        u16::from_le_bytes([bytes[at], bytes[at + 1]]) as usize
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn column_name_carries_past_z() {
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(27), "AB");
+        assert_eq!(column_name(701), "ZZ");
+        assert_eq!(column_name(702), "AAA");
+    }
+
+    #[test]
+    fn zip_archive_stores_mimetype_and_deflates_the_rest() {
+        let content = b"<office:document-content/>".repeat(20);
+        let files = vec![
+            (
+                String::from("mimetype"),
+                b"application/vnd.oasis.opendocument.spreadsheet".to_vec(),
+            ),
+            (String::from("content.xml"), content.clone()),
+        ];
+        let archive = zip_archive(&files);
+
+        // mimetype comes first and uncompressed, so its name is at a fixed offset
+        assert_eq!(u32_at(&archive, 0), 0x04034b50);
+        assert_eq!(u16_at(&archive, 8), 0);
+        assert_eq!(&archive[30..38], b"mimetype");
+        assert_eq!(&archive[38..38 + files[0].1.len()], files[0].1.as_slice());
+
+        let second = 38 + files[0].1.len();
+        assert_eq!(u32_at(&archive, second), 0x04034b50);
+        assert_eq!(u16_at(&archive, second + 8), 8);
+        assert_eq!(u32_at(&archive, second + 14), crc32(&content));
+        let compressed_size = u32_at(&archive, second + 18) as usize;
+        let data_start = second + 30 + u16_at(&archive, second + 26);
+        let inflated = miniz_oxide::inflate::decompress_to_vec(
+            &archive[data_start..data_start + compressed_size],
+        )
+        .unwrap();
+        assert_eq!(inflated, content);
+
+        let end = archive.len() - 22;
+        assert_eq!(u32_at(&archive, end), 0x06054b50);
+        assert_eq!(u16_at(&archive, end + 10), 2);
+        let directory_offset = u32_at(&archive, end + 16) as usize;
+        assert_eq!(directory_offset, data_start + compressed_size);
+        assert_eq!(u32_at(&archive, directory_offset), 0x02014b50);
+    }
+}